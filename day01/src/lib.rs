@@ -1,14 +1,20 @@
 use aoclib::parse;
 use color_eyre::{
-    eyre::{format_err, Report},
+    eyre::{format_err, Context, OptionExt, Report},
     Result,
 };
 use std::{path::Path, str::FromStr};
 
-const DIAL_SIZE: i32 = 100;
-const INITIAL_POSITION: i32 = 50;
+/// Dial positions, rotation quantities, and counts of clicks.
+///
+/// Instructions may specify rotations well beyond the range of an `i32`, so we just go wide. Quantities are
+/// unsigned; the direction of a rotation is kept separately.
+type Clicks = u128;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display, parse_display::FromStr)]
+const DIAL_SIZE: Clicks = 100;
+const INITIAL_POSITION: Clicks = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display)]
 enum Direction {
     #[display("L")]
     Left,
@@ -16,83 +22,231 @@ enum Direction {
     Right,
 }
 
+impl Direction {
+    /// Parse a direction from its character, ignoring case.
+    fn from_char(c: char) -> Option<Self> {
+        match c.to_ascii_uppercase() {
+            'L' => Some(Self::Left),
+            'R' => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    fn reverse(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display)]
 #[display("{direction}{qty}")]
 struct Instruction {
     direction: Direction,
-    qty: i32,
+    qty: Clicks,
 }
 
 impl FromStr for Instruction {
     type Err = Report;
 
+    /// Parse a single instruction.
+    ///
+    /// Accepted forms are a direction followed by a quantity (`L5`, `r12`), a direction followed by a
+    /// signed quantity (`L+5`, `R-5`), or a bare signed quantity (`+5`, `-5`), where positive values turn
+    /// right. Negative quantities reverse the direction, so the parsed quantity is never negative.
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        if !s.is_char_boundary(1) {
-            return Err(format_err!("index 1 not a unicode boundary in {}", s));
+        let first = s.chars().next().ok_or_eyre("empty instruction")?;
+        let (direction, signed_qty) = match Direction::from_char(first) {
+            Some(direction) => (direction, &s[first.len_utf8()..]),
+            None if s.starts_with(['+', '-']) => (Direction::Right, s),
+            None => return Err(format_err!("unknown direction '{first}'")),
+        };
+        // strip the sign ourselves, so that the magnitude of any negative quantity fits
+        let (direction, qty) = match signed_qty.strip_prefix('-') {
+            Some(qty) => (direction.reverse(), qty),
+            None => (
+                direction,
+                signed_qty.strip_prefix('+').unwrap_or(signed_qty),
+            ),
+        };
+        if !qty.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(format_err!(
+                "parsing quantity {signed_qty:?}: expected digits"
+            ));
         }
-        let (direction, qty) = s.split_at(1);
-        let direction = direction.parse()?;
-        let qty = qty.parse()?;
+        let qty = qty
+            .parse::<Clicks>()
+            .wrap_err_with(|| format!("parsing quantity {signed_qty:?}"))?;
         Ok(Self { direction, qty })
     }
 }
 
 impl Instruction {
-    fn motion(&self) -> i32 {
+    /// Turn the dial from `position`, which must be in `0..DIAL_SIZE`.
+    ///
+    /// Returns the new position and the number of clicks during the turn, including the last, at which the
+    /// dial points at 0. Neither computation can overflow, however large the quantity.
+    fn turn(&self, position: Clicks) -> (Clicks, Clicks) {
+        let (spins, remainder) = (self.qty / DIAL_SIZE, self.qty % DIAL_SIZE);
         match self.direction {
-            Direction::Left => -self.qty,
-            Direction::Right => self.qty,
+            Direction::Right => {
+                let end = position + remainder;
+                (end % DIAL_SIZE, spins + end / DIAL_SIZE)
+            }
+            Direction::Left => {
+                // within the final partial spin, we reach 0 only if we started past it
+                let reaches_zero = position != 0 && remainder >= position;
+                let end = (position + DIAL_SIZE - remainder) % DIAL_SIZE;
+                (end, spins + Clicks::from(reaches_zero))
+            }
         }
     }
 }
 
-pub fn part1(input: &Path) -> Result<()> {
-    let mut position = INITIAL_POSITION;
-    let mut zero_count = 0;
-    for instruction in parse::<Instruction>(input)? {
-        position += instruction.motion();
+/// A line of input, containing any number of instructions separated by commas and/or whitespace.
+#[derive(Debug)]
+struct InstructionLine(Vec<Instruction>);
 
-        if position.rem_euclid(DIAL_SIZE) == 0 {
-            zero_count += 1;
-        }
+impl FromStr for InstructionLine {
+    type Err = Report;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        s.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty())
+            .enumerate()
+            .map(|(idx, token)| {
+                token
+                    .parse()
+                    .map_err(|err| format_err!("token {} ({token:?}): {err:#}", idx + 1))
+            })
+            .collect::<Result<_>>()
+            .map(Self)
     }
-    println!("zero count: {zero_count}");
-    Ok(())
 }
 
-pub fn part2(input: &Path) -> Result<()> {
+fn instructions(input: &Path) -> Result<impl Iterator<Item = Instruction>> {
+    Ok(parse::<InstructionLine>(input)?.flat_map(|line| line.0))
+}
+
+/// Count the clicks at which the dial points at 0.
+///
+/// When `passing` is set, every click of a turn counts; otherwise only the position at the end of each turn.
+fn count_zeros(
+    instructions: impl IntoIterator<Item = Instruction>,
+    passing: bool,
+) -> Result<Clicks> {
     let mut position = INITIAL_POSITION;
-    let mut zero_count = 0;
-
-    for instruction in parse::<Instruction>(input)? {
-        let next_position = position + instruction.motion();
-
-        let position_bounded = position.rem_euclid(DIAL_SIZE);
-        let next_position_bounded = next_position.rem_euclid(DIAL_SIZE);
-
-        let mut passed_zeros = {
-            // "day" is a bad term here, but I'm blanking on anything better.
-            // it's an identifier for a particular 0-99 range of the dial;
-            // a full spin of the wheel.
-            // if we're in the same day, we have not clicked past 0.
-            // If we're in a different day, we have clicked past 0 some amount of times.
-            let start_dial_day = position.div_euclid(DIAL_SIZE);
-            let end_dial_day = next_position.div_euclid(DIAL_SIZE);
-            start_dial_day.abs_diff(end_dial_day)
+    let mut zero_count: Clicks = 0;
+    for instruction in instructions {
+        let (next_position, passed_zeros) = instruction.turn(position);
+        let zeros = if passing {
+            passed_zeros
+        } else {
+            Clicks::from(next_position == 0)
         };
-        // a limitaiton of the day count mechanism: left turns from 0 produce an extra false count
-        if position_bounded == 0 && instruction.direction == Direction::Left && passed_zeros > 0 {
-            passed_zeros -= 1;
-        }
-        // a second limitation of the day count mechanism: left turns onto 0 undercount
-        if next_position_bounded == 0 && instruction.direction == Direction::Left {
-            passed_zeros += 1;
-        }
-
-        zero_count += passed_zeros;
+        zero_count = zero_count
+            .checked_add(zeros)
+            .ok_or_else(|| format_err!("zero count overflows at instruction {instruction}"))?;
         position = next_position;
     }
+    Ok(zero_count)
+}
 
+pub fn part1(input: &Path) -> Result<()> {
+    let zero_count = count_zeros(instructions(input)?, false)?;
+    println!("zero count: {zero_count}");
+    Ok(())
+}
+
+pub fn part2(input: &Path) -> Result<()> {
+    let zero_count = count_zeros(instructions(input)?, true)?;
     println!("zero count (pt 2): {zero_count}");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Vec<Instruction> {
+        line.parse::<InstructionLine>().unwrap().0
+    }
+
+    fn counts(lines: &[&str]) -> (Clicks, Clicks) {
+        let instructions = lines
+            .iter()
+            .flat_map(|line| parse_line(line))
+            .collect::<Vec<_>>();
+        (
+            count_zeros(instructions.clone(), false).unwrap(),
+            count_zeros(instructions, true).unwrap(),
+        )
+    }
+
+    const EXAMPLE: [&str; 10] = [
+        "L68", "L30", "R48", "L5", "R60", "L55", "L1", "L99", "R14", "L82",
+    ];
+
+    #[test]
+    fn instruction_forms() {
+        let left = Instruction {
+            direction: Direction::Left,
+            qty: 5,
+        };
+        let right = Instruction {
+            direction: Direction::Right,
+            qty: 5,
+        };
+        assert_eq!(parse_line("l5 r5"), [left, right]);
+        assert_eq!(parse_line("L+5 R-5"), [left, left]);
+        assert_eq!(parse_line("+5 -5"), [right, left]);
+        assert_eq!(parse_line("R+5 L-5"), [right, right]);
+
+        // the magnitude of the most negative `i128` is not itself an `i128`
+        let [instruction] = parse_line("-170141183460469231731687303715884105728")[..] else {
+            panic!("expected one instruction");
+        };
+        assert_eq!(instruction.direction, Direction::Left);
+        assert_eq!(instruction.qty, 1 << 127);
+    }
+
+    #[test]
+    fn separated_streams_match_example() {
+        assert_eq!(counts(&EXAMPLE), (3, 6));
+        assert_eq!(counts(&[&EXAMPLE.join(",")]), (3, 6));
+        assert_eq!(counts(&[&EXAMPLE.join(" ")]), (3, 6));
+        assert_eq!(counts(&[&EXAMPLE.join(", \t")]), (3, 6));
+        assert_eq!(
+            counts(&[&EXAMPLE[..4].join(","), &EXAMPLE[4..].join(" ")]),
+            (3, 6)
+        );
+    }
+
+    #[test]
+    fn huge_turns_do_not_overflow() {
+        assert_eq!(
+            counts(&["R170141183460469231731687303715884105727", "R5"]),
+            (0, 1701411834604692317316873037158841057)
+        );
+        // from 50, a right turn of 50 + 100n reaches 0 on n + 1 clicks
+        let max = Clicks::MAX;
+        let right = (max - 50) / DIAL_SIZE * DIAL_SIZE + 50;
+        assert_eq!(
+            counts(&[&format!("R{right}")]),
+            (1, (right - 50) / DIAL_SIZE + 1)
+        );
+        assert_eq!(counts(&[&format!("L{max}")]).1, max / DIAL_SIZE + 1);
+    }
+
+    #[test]
+    fn errors_name_the_token() {
+        let err = "L5 X3".parse::<InstructionLine>().unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.contains("token 2 (\"X3\")"), "{message}");
+        assert!(message.contains("unknown direction 'X'"), "{message}");
+
+        let message = format!("{:#}", "R5,L--5".parse::<InstructionLine>().unwrap_err());
+        assert!(message.contains("token 2 (\"L--5\")"), "{message}");
+    }
+}
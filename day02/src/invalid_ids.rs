@@ -0,0 +1,138 @@
+//! Directly enumerate the invalid product IDs within a range.
+//!
+//! An invalid ID is one whose representation in some radix is a pattern of digits repeated some number of times.
+//! Rather than testing every ID in a range, we can generate exactly those numbers: a pattern `p` of `len` digits
+//! repeated `k` times is `p * m`, where `m = (r^(len*k) - 1) / (r^len - 1)` for radix `r`. For example, in decimal
+//! with `len = 3` and `k = 3`, `m = 1001001`, and `123 * m = 123123123`.
+//!
+//! For each digit length in the range, we can therefore compute the span of patterns which land inside the range
+//! and emit their products directly, in time proportional to the number of invalid IDs.
+//!
+//! Powers of the radix can exceed even a `u128` near the top of the domain, so they are computed with checked
+//! arithmetic: a power which overflows is necessarily greater than any ID in the range.
+
+use crate::ProductId;
+use itertools::Itertools as _;
+use std::fmt;
+
+fn pow(radix: u32, exponent: u32) -> Option<ProductId> {
+    ProductId::from(radix).checked_pow(exponent)
+}
+
+/// `radix^n_digits - 1`, saturating at `ProductId::MAX`.
+fn max_with_n_digits(radix: u32, n_digits: u32) -> ProductId {
+    pow(radix, n_digits).map_or(ProductId::MAX, |power| power - 1)
+}
+
+pub(crate) fn n_digits(id: ProductId, radix: u32) -> u32 {
+    id.checked_ilog(radix.into()).unwrap_or_default() + 1
+}
+
+/// The multiplier which repeats a pattern of `pattern_len` digits `repetitions` times.
+///
+/// In the given radix, this is `1` followed by `repetitions - 1` copies of `pattern_len - 1` zeros and a `1`.
+/// Returns `None` if the multiplier overflows, in which case every repeated pattern would also overflow.
+fn multiplier(radix: u32, pattern_len: u32, repetitions: u32) -> Option<ProductId> {
+    let shift = pow(radix, pattern_len)?;
+    (1..repetitions).try_fold(1, |multiplier: ProductId, _| {
+        multiplier.checked_mul(shift)?.checked_add(1)
+    })
+}
+
+/// Enumerate the invalid IDs in an inclusive range, in ascending order.
+///
+/// `accept` is a predicate over a pattern length and repetition count: an ID is invalid if it can be
+/// represented as a pattern of `len` digits repeated `k` times for any `(len, k)` for which the predicate holds.
+/// Repetition counts less than 2 are never considered.
+pub(crate) fn invalid_ids(
+    first: ProductId,
+    last: ProductId,
+    radix: u32,
+    accept: impl Fn(u32, u32) -> bool,
+) -> impl Iterator<Item = ProductId> {
+    (n_digits(first, radix)..=n_digits(last, radix)).flat_map(move |n_digits| {
+        let low = first.max(
+            pow(radix, n_digits - 1).expect("a power no greater than `last` does not overflow"),
+        );
+        let high = last.min(max_with_n_digits(radix, n_digits));
+
+        // one ascending progression for each acceptable repetition count
+        let progressions = (2..=n_digits)
+            .filter(|repetitions| n_digits.is_multiple_of(*repetitions))
+            .filter(|repetitions| accept(n_digits / repetitions, *repetitions))
+            .filter_map(|repetitions| {
+                let pattern_len = n_digits / repetitions;
+                let multiplier = multiplier(radix, pattern_len, repetitions)?;
+                let pattern_low = pow(radix, pattern_len - 1)?.max(low.div_ceil(multiplier));
+                let pattern_high = max_with_n_digits(radix, pattern_len).min(high / multiplier);
+                Some((pattern_low..=pattern_high).map(move |pattern| pattern * multiplier))
+            })
+            .collect::<Vec<_>>();
+
+        // an ID like `111111` is generated once each for 2, 3, and 6 repetitions
+        progressions.into_iter().kmerge().dedup()
+    })
+}
+
+/// The repeating unit of an invalid ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Repetition {
+    pub(crate) pattern: ProductId,
+    pub(crate) pattern_len: u32,
+    pub(crate) repetitions: u32,
+    pub(crate) radix: u32,
+}
+
+impl Repetition {
+    /// Find the shortest pattern which, repeated an acceptable number of times, produces `id`.
+    ///
+    /// `accept` is a predicate over a pattern length and repetition count, as for [`invalid_ids`].
+    ///
+    /// Like `id_is_valid_pt2`, this considers chunk sizes in ascending order, so when accepting any
+    /// repetition count it produces the smallest repeating unit. Returns `None` if `id` is valid.
+    pub(crate) fn of(id: ProductId, radix: u32, accept: impl Fn(u32, u32) -> bool) -> Option<Self> {
+        let n_digits = n_digits(id, radix);
+        (1..=n_digits / 2)
+            .filter(|pattern_len| n_digits.is_multiple_of(*pattern_len))
+            .filter(|pattern_len| accept(*pattern_len, n_digits / pattern_len))
+            .find_map(|pattern_len| {
+                let repetitions = n_digits / pattern_len;
+                let multiplier = multiplier(radix, pattern_len, repetitions)?;
+                id.is_multiple_of(multiplier).then(|| Self {
+                    pattern: id / multiplier,
+                    pattern_len,
+                    repetitions,
+                    radix,
+                })
+            })
+    }
+}
+
+impl fmt::Display for Repetition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" x{}",
+            InRadix(self.pattern, self.radix),
+            self.repetitions
+        )
+    }
+}
+
+/// Display an ID in the given radix.
+pub(crate) struct InRadix(pub(crate) ProductId, pub(crate) u32);
+
+impl fmt::Display for InRadix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self(id, radix) = *self;
+        for exponent in (0..n_digits(id, radix)).rev() {
+            let place =
+                pow(radix, exponent).expect("a power no greater than `id` does not overflow");
+            let digit = (id / place) % ProductId::from(radix);
+            let digit = char::from_digit(digit as u32, radix)
+                .expect("a value reduced modulo the radix is a valid digit");
+            write!(f, "{digit}")?;
+        }
+        Ok(())
+    }
+}
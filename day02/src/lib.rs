@@ -1,28 +1,133 @@
+mod invalid_ids;
+
 use aoclib::{parse, CommaSep};
 use color_eyre::{
-    eyre::{Context, OptionExt, Report},
+    eyre::{Context, OptionExt},
     Result,
 };
-use itertools::Itertools as _;
-use std::{path::Path, str::FromStr};
+use invalid_ids::{InRadix, Repetition};
+use range_set::RangeSet;
+use std::{collections::BTreeMap, ops::RangeInclusive, path::Path};
+
+type ProductId = u128;
+
+/// Options controlling how invalid ids are counted and reported.
+#[derive(Debug, Clone, Copy, clap::Args)]
+pub struct Options {
+    /// radix in which product ids are written
+    ///
+    /// ids are repeated patterns of digits in this radix
+    #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(2..=36))]
+    pub radix: u32,
+
+    /// count invalid ids in overlapping ranges once per range which contains them
+    ///
+    /// by default, overlapping and adjacent ranges within a row are merged before evaluation
+    #[arg(long)]
+    pub keep_overlaps: bool,
+
+    /// list each invalid id with its range and repeating pattern
+    #[arg(long)]
+    pub list: bool,
+
+    /// summarize invalid ids by the length of their repeating pattern
+    ///
+    /// when combined with `--list`, ids are listed within their groups
+    #[arg(long)]
+    pub group_by_pattern_len: bool,
+}
+
+/// A selection of invalid ids according to the shape of their repetition.
+///
+/// An id is selected if it can be represented as some pattern repeated some number of times, where the
+/// pattern length and repetition count satisfy every constraint which is set. With no constraints set,
+/// every invalid id is selected.
+#[derive(Debug, Default, Clone, clap::Args)]
+pub struct Query {
+    /// select ids which are a pattern repeated exactly this many times
+    #[arg(
+        long,
+        value_name = "K",
+        conflicts_with = "min_repetitions",
+        value_parser = clap::value_parser!(u32).range(2..)
+    )]
+    pub repetitions: Option<u32>,
+
+    /// select ids which are a pattern repeated at least this many times
+    #[arg(long, value_name = "K", value_parser = clap::value_parser!(u32).range(2..))]
+    pub min_repetitions: Option<u32>,
+
+    /// select ids whose repeating pattern has one of these lengths
+    #[arg(
+        long,
+        value_name = "LEN",
+        value_delimiter = ',',
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub pattern_len: Vec<u32>,
+}
+
+impl Query {
+    /// Part 1: ids made of a pattern repeated exactly twice.
+    fn part1() -> Self {
+        Self {
+            repetitions: Some(2),
+            ..Self::default()
+        }
+    }
+
+    /// Part 2: ids made of a pattern repeated at least twice.
+    fn part2() -> Self {
+        Self {
+            min_repetitions: Some(2),
+            ..Self::default()
+        }
+    }
+
+    /// `true` if any constraint is set.
+    pub fn is_constrained(&self) -> bool {
+        self.repetitions.is_some() || self.min_repetitions.is_some() || !self.pattern_len.is_empty()
+    }
+
+    fn accepts(&self, pattern_len: u32, repetitions: u32) -> bool {
+        self.repetitions.is_none_or(|exact| repetitions == exact)
+            && self.min_repetitions.is_none_or(|min| repetitions >= min)
+            && (self.pattern_len.is_empty() || self.pattern_len.contains(&pattern_len))
+    }
+}
+
+/// Aggregate statistics over the invalid ids selected by a query.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Stats {
+    count: u64,
+    sum: ProductId,
+    min: Option<ProductId>,
+    max: Option<ProductId>,
+}
 
-type ProductId = u64;
+impl Stats {
+    fn insert(&mut self, id: ProductId) -> Result<()> {
+        self.count += 1;
+        self.sum = self
+            .sum
+            .checked_add(id)
+            .ok_or_eyre("sum of invalid ids overflowed")?;
+        self.min = Some(self.min.map_or(id, |min| min.min(id)));
+        self.max = Some(self.max.map_or(id, |max| max.max(id)));
+        Ok(())
+    }
+}
 
+#[derive(Debug, Clone, Copy)]
 struct ProductIdRange {
     first_id: ProductId,
     last_id: ProductId,
 }
 
-impl FromStr for ProductIdRange {
-    type Err = Report;
-
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
-        let hyphen_position = s.find('-').ok_or_eyre("no hyphen")?;
-        let (first_id, s) = s.split_at(hyphen_position);
-        let (_, last_id) = s.split_at(1);
-        let first_id = first_id.parse().wrap_err("parsing first id")?;
-        let last_id = last_id.parse().wrap_err("parsing last id")?;
-        Ok(Self { first_id, last_id })
+impl From<RangeInclusive<ProductId>> for ProductIdRange {
+    fn from(range: RangeInclusive<ProductId>) -> Self {
+        let (first_id, last_id) = range.into_inner();
+        Self { first_id, last_id }
     }
 }
 
@@ -37,26 +142,219 @@ impl IntoIterator for ProductIdRange {
     }
 }
 
-fn id_is_valid_pt1(id: ProductId) -> bool {
-    let s = id.to_string();
-    let half_len = s.len() / 2;
-    if !s.is_char_boundary(half_len) {
-        eprintln!("WARNING: half_len was not on char boundary");
-        return false;
+impl ProductIdRange {
+    fn from_str_radix(s: &str, radix: u32) -> Result<Self> {
+        let s = s.trim();
+        let hyphen_position = s.find('-').ok_or_eyre("no hyphen")?;
+        let (first_id, s) = s.split_at(hyphen_position);
+        let (_, last_id) = s.split_at(1);
+        let first_id = ProductId::from_str_radix(first_id, radix).wrap_err("parsing first id")?;
+        let last_id = ProductId::from_str_radix(last_id, radix).wrap_err("parsing last id")?;
+        Ok(Self { first_id, last_id })
+    }
+
+    fn contains(&self, id: ProductId) -> bool {
+        self.first_id <= id && id <= self.last_id
+    }
+
+    /// Enumerate the invalid IDs in this range which are selected by `query`, without visiting every ID.
+    fn invalid_ids<'a>(
+        &self,
+        radix: u32,
+        query: &'a Query,
+    ) -> impl 'a + Iterator<Item = ProductId> {
+        invalid_ids::invalid_ids(
+            self.first_id,
+            self.last_id,
+            radix,
+            |pattern_len, repetitions| query.accepts(pattern_len, repetitions),
+        )
+    }
+}
+
+/// A row of ranges after merging those which overlap or are adjacent.
+struct ConsolidatedRow {
+    ranges: Vec<ProductIdRange>,
+    /// Portions of input ranges which were already covered by some earlier range.
+    ///
+    /// Every invalid ID in an overlap would have been counted more than once without consolidation.
+    overlaps: Vec<ProductIdRange>,
+}
+
+impl ConsolidatedRow {
+    fn new(mut row: Vec<ProductIdRange>) -> Self {
+        row.sort_unstable_by_key(|range| range.first_id);
+
+        let mut union = RangeSet::new();
+        let mut overlaps = Vec::new();
+        for range in row {
+            // each id in an overlap is covered once more than it was before this range was added
+            let range = range.into_iter();
+            let overlap = RangeSet::from_iter([range.clone()]).intersection(&union);
+            overlaps.extend(overlap.iter().map(ProductIdRange::from));
+            union.insert(range);
+        }
+
+        Self {
+            ranges: union.iter().map(ProductIdRange::from).collect(),
+            overlaps,
+        }
     }
-    let (first_half, second_half) = s.split_at(half_len);
-    first_half.len() != second_half.len() || first_half != second_half
 }
 
-fn id_is_valid_pt2(id: ProductId) -> bool {
-    let s = id.to_string();
-    let s = s.as_bytes();
-    for chunk_size in 1..=(s.len() / 2) {
-        if !s.len().is_multiple_of(chunk_size) {
+/// The first range of the input row which contains `id`.
+///
+/// Listings name the range an id came from, even when it was found by enumerating a merged range.
+fn source_range(row: &[ProductIdRange], id: ProductId) -> ProductIdRange {
+    row.iter()
+        .copied()
+        .find(|range| range.contains(id))
+        .expect("every enumerated id lies within some input range")
+}
+
+/// Invalid ids sharing a pattern length.
+#[derive(Default)]
+struct PatternLenGroup {
+    stats: Stats,
+    /// only populated when listing
+    listing: Vec<(ProductId, ProductIdRange, Repetition)>,
+}
+
+fn print_listing_entry(id: ProductId, range: ProductIdRange, repetition: Repetition) {
+    let radix = repetition.radix;
+    println!(
+        "  {} = {repetition} ({}-{})",
+        InRadix(id, radix),
+        InRadix(range.first_id, radix),
+        InRadix(range.last_id, radix),
+    );
+}
+
+fn print_stats(indent: &str, stats: Stats, radix: u32) {
+    let in_radix =
+        |id: Option<ProductId>| id.map_or_else(|| "-".into(), |id| InRadix(id, radix).to_string());
+    println!(
+        "{indent}count: {}, min: {}, max: {}",
+        stats.count,
+        in_radix(stats.min),
+        in_radix(stats.max),
+    );
+    if radix == 10 {
+        println!("{indent}sum of invalid ids: {}", stats.sum);
+    } else {
+        println!(
+            "{indent}sum of invalid ids: {} (decimal {})",
+            InRadix(stats.sum, radix),
+            stats.sum
+        );
+    }
+}
+
+/// Report the invalid IDs selected by `query` in each row of the input.
+///
+/// Unless `options.keep_overlaps` is set, overlapping and adjacent ranges within a row are merged first so
+/// that each invalid ID is counted only once.
+fn solve(input: &Path, label: &str, options: Options, query: &Query) -> Result<()> {
+    let radix = options.radix;
+    let accept = |pattern_len, repetitions| query.accepts(pattern_len, repetitions);
+
+    for (n, row) in parse::<CommaSep<String>>(input)?.enumerate() {
+        println!("{label} row {n}:");
+
+        let mut ranges = row
+            .into_iter()
+            .enumerate()
+            .map(|(idx, range)| {
+                ProductIdRange::from_str_radix(&range, radix)
+                    .wrap_err_with(|| format!("parsing range {idx} of row {n}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let row = ranges.clone();
+        if !options.keep_overlaps {
+            let consolidated = ConsolidatedRow::new(ranges);
+            let duplicates = consolidated
+                .overlaps
+                .iter()
+                .flat_map(|overlap| overlap.invalid_ids(radix, query))
+                .count();
+            println!(" duplicate invalid ids removed: {duplicates}");
+            ranges = consolidated.ranges;
+        }
+
+        let mut stats = Stats::default();
+        let mut groups = BTreeMap::<u32, PatternLenGroup>::new();
+        for range in ranges {
+            for id in range.invalid_ids(radix, query) {
+                stats.insert(id)?;
+                if !(options.list || options.group_by_pattern_len) {
+                    continue;
+                }
+
+                let repetition = Repetition::of(id, radix, accept)
+                    .expect("every enumerated invalid id has a repeating pattern");
+                let range = if options.keep_overlaps {
+                    range
+                } else {
+                    source_range(&row, id)
+                };
+                if options.group_by_pattern_len {
+                    let group = groups.entry(repetition.pattern_len).or_default();
+                    group.stats.insert(id)?;
+                    if options.list {
+                        group.listing.push((id, range, repetition));
+                    }
+                } else {
+                    print_listing_entry(id, range, repetition);
+                }
+            }
+        }
+
+        for (pattern_len, group) in groups {
+            println!(" pattern length {pattern_len}:");
+            print_stats("  ", group.stats, radix);
+            for (id, range, repetition) in group.listing {
+                print_listing_entry(id, range, repetition);
+            }
+        }
+
+        print_stats(" ", stats, radix);
+    }
+
+    Ok(())
+}
+
+// These brute-force checks are retained as the reference against which `invalid_ids` is tested.
+
+/// Does `id` consist entirely of repetitions of its lowest `chunk_len` digits?
+#[cfg(test)]
+fn is_repeated_chunk(id: ProductId, radix: u32, chunk_len: u32) -> bool {
+    let place = ProductId::from(radix).pow(chunk_len);
+    let chunk = id % place;
+    let mut rest = id / place;
+    while rest > 0 {
+        if rest % place != chunk {
+            return false;
+        }
+        rest /= place;
+    }
+    true
+}
+
+#[cfg(test)]
+fn id_is_valid_pt1(id: ProductId, radix: u32) -> bool {
+    let n_digits = invalid_ids::n_digits(id, radix);
+    !n_digits.is_multiple_of(2) || !is_repeated_chunk(id, radix, n_digits / 2)
+}
+
+#[cfg(test)]
+fn id_is_valid_pt2(id: ProductId, radix: u32) -> bool {
+    let n_digits = invalid_ids::n_digits(id, radix);
+    for chunk_len in 1..=(n_digits / 2) {
+        if !n_digits.is_multiple_of(chunk_len) {
             continue;
         }
 
-        if s.chunks(chunk_size).all_equal() {
+        if is_repeated_chunk(id, radix, chunk_len) {
             return false;
         };
     }
@@ -64,32 +362,241 @@ fn id_is_valid_pt2(id: ProductId) -> bool {
     true
 }
 
-pub fn part1(input: &Path) -> Result<()> {
-    for (n, row) in parse::<CommaSep<ProductIdRange>>(input)?.enumerate() {
-        println!("part 1 row {n}:");
+pub fn part1(input: &Path, options: Options) -> Result<()> {
+    solve(input, "part 1", options, &Query::part1())
+}
+
+pub fn part2(input: &Path, options: Options) -> Result<()> {
+    solve(input, "part 2", options, &Query::part2())
+}
+
+/// Report the invalid ids selected by an arbitrary query.
+pub fn query(input: &Path, options: Options, query: &Query) -> Result<()> {
+    solve(input, "query", options, query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        let sum_invalid_ids = row
+    fn check_against_brute_force(first_id: ProductId, last_id: ProductId, radix: u32) {
+        let range = ProductIdRange { first_id, last_id };
+        let brute_force_pt1 = range
             .into_iter()
-            .flat_map(|id_range| id_range.into_iter())
-            .filter(|id| !id_is_valid_pt1(*id))
-            .sum::<u64>();
-        println!(" sum of invalid ids: {sum_invalid_ids}");
+            .filter(|id| !id_is_valid_pt1(*id, radix))
+            .collect::<Vec<_>>();
+        let brute_force_pt2 = range
+            .into_iter()
+            .filter(|id| !id_is_valid_pt2(*id, radix))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            range
+                .invalid_ids(radix, &Query::part1())
+                .collect::<Vec<_>>(),
+            brute_force_pt1,
+            "pt1 {first_id}-{last_id} radix {radix}"
+        );
+        assert_eq!(
+            range
+                .invalid_ids(radix, &Query::part2())
+                .collect::<Vec<_>>(),
+            brute_force_pt2,
+            "pt2 {first_id}-{last_id} radix {radix}"
+        );
     }
 
-    Ok(())
-}
+    #[test]
+    fn enumeration_matches_brute_force() {
+        check_against_brute_force(0, 1_200_000, 10);
+        for (first_id, last_id) in [
+            (11, 22),
+            (95, 115),
+            (998, 1012),
+            (1188511880, 1188511890),
+            (222220, 222224),
+            (1698522, 1698528),
+            (446443, 446449),
+            (38593856, 38593862),
+            (565653, 565659),
+            (824824821, 824824827),
+            (2121212118, 2121212124),
+            (9_999_999_000, 10_000_001_000),
+        ] {
+            check_against_brute_force(first_id, last_id, 10);
+        }
+    }
+
+    #[test]
+    fn enumeration_matches_brute_force_in_other_radices() {
+        for radix in [2, 3, 7, 16, 36] {
+            check_against_brute_force(0, 200_000, radix);
+        }
+        check_against_brute_force(0xfff_f000, 0x1000_0fff, 16);
+    }
+
+    #[test]
+    fn enumeration_handles_extreme_ranges() {
+        let range = ProductIdRange {
+            first_id: ProductId::MAX - 1_000_000,
+            last_id: ProductId::MAX,
+        };
+        assert_eq!(range.invalid_ids(10, &Query::part2()).count(), 0);
+
+        let range = ProductIdRange {
+            first_id: 18446744071844674400,
+            last_id: u64::MAX.into(),
+        };
+        assert_eq!(
+            range.invalid_ids(10, &Query::part2()).collect::<Vec<_>>(),
+            vec![18446744071844674407]
+        );
+
+        let range = ProductIdRange {
+            first_id: 340282366920934028236692093402823669200,
+            last_id: ProductId::MAX,
+        };
+        assert_eq!(
+            range.invalid_ids(10, &Query::part2()).collect::<Vec<_>>(),
+            vec![340282366920934028236692093402823669209]
+        );
 
-pub fn part2(input: &Path) -> Result<()> {
-    for (n, row) in parse::<CommaSep<ProductIdRange>>(input)?.enumerate() {
-        println!("part 2 row {n}:");
+        // in binary, `u128::MAX` is 128 ones
+        let range = ProductIdRange {
+            first_id: ProductId::MAX - 1,
+            last_id: ProductId::MAX,
+        };
+        assert_eq!(
+            range.invalid_ids(2, &Query::part2()).collect::<Vec<_>>(),
+            vec![ProductId::MAX]
+        );
+    }
+
+    #[test]
+    fn consolidation_merges_overlapping_and_adjacent_ranges() {
+        let row = [(50, 60), (10, 20), (15, 30), (31, 40), (12, 18), (70, 70)]
+            .into_iter()
+            .map(|(first_id, last_id)| ProductIdRange { first_id, last_id })
+            .collect();
+        let consolidated = ConsolidatedRow::new(row);
+        let as_tuples = |ranges: &[ProductIdRange]| {
+            ranges
+                .iter()
+                .map(|range| (range.first_id, range.last_id))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            as_tuples(&consolidated.ranges),
+            [(10, 40), (50, 60), (70, 70)]
+        );
+        assert_eq!(as_tuples(&consolidated.overlaps), [(12, 18), (15, 20)]);
+
+        // listings name the first input range containing each id, not the merged range
+        let row = [(11, 22), (15, 33)]
+            .into_iter()
+            .map(|(first_id, last_id)| ProductIdRange { first_id, last_id })
+            .collect::<Vec<_>>();
+        let sources = [11, 22, 33]
+            .map(|id| source_range(&row, id))
+            .map(|range| (range.first_id, range.last_id));
+        assert_eq!(sources, [(11, 22), (11, 22), (15, 33)]);
 
-        let sum_invalid_ids = row
+        // 11 is covered by two ranges and 22 by three, for three duplicates in total
+        let row = [(10, 25), (20, 35), (5, 22)]
             .into_iter()
-            .flat_map(|id_range| id_range.into_iter())
-            .filter(|id| !id_is_valid_pt2(*id))
-            .sum::<u64>();
-        println!(" sum of invalid ids: {sum_invalid_ids}");
+            .map(|(first_id, last_id)| ProductIdRange { first_id, last_id })
+            .collect();
+        let consolidated = ConsolidatedRow::new(row);
+        assert_eq!(as_tuples(&consolidated.ranges), [(5, 35)]);
+        let query = Query::part1();
+        assert_eq!(
+            consolidated
+                .overlaps
+                .iter()
+                .flat_map(|overlap| overlap.invalid_ids(10, &query))
+                .collect::<Vec<_>>(),
+            [11, 22, 22]
+        );
     }
 
-    Ok(())
+    #[test]
+    fn repetition_finds_smallest_acceptable_pattern() {
+        let repetition = |id, query: Query| {
+            Repetition::of(id, 10, |len, k| query.accepts(len, k))
+                .map(|repetition| (repetition.pattern, repetition.repetitions))
+        };
+        assert_eq!(repetition(123123123, Query::part2()), Some((123, 3)));
+        assert_eq!(repetition(123123123, Query::part1()), None);
+        assert_eq!(repetition(111111, Query::part2()), Some((1, 6)));
+        assert_eq!(repetition(111111, Query::part1()), Some((111, 2)));
+        assert_eq!(repetition(12341234, Query::part2()), Some((1234, 2)));
+        assert_eq!(repetition(12341235, Query::part2()), None);
+
+        let repetition = Repetition::of(0xabcabc, 16, |_, k| k >= 2).unwrap();
+        assert_eq!(repetition.to_string(), "\"abc\" x2");
+        assert_eq!(InRadix(0, 2).to_string(), "0");
+        assert_eq!(
+            InRadix(ProductId::MAX, 36).to_string(),
+            "f5lxx1zz5pnorynqglhzmsp33"
+        );
+    }
+
+    #[test]
+    fn queries_match_brute_force() {
+        let range = ProductIdRange {
+            first_id: 0,
+            last_id: 1_200_000,
+        };
+        let brute_force = |accept: &dyn Fn(u32, u32) -> bool| {
+            range
+                .into_iter()
+                .filter(|&id| {
+                    let n_digits = invalid_ids::n_digits(id, 10);
+                    (1..=n_digits / 2).any(|len| {
+                        n_digits.is_multiple_of(len)
+                            && accept(len, n_digits / len)
+                            && is_repeated_chunk(id, 10, len)
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let exactly_three = Query {
+            repetitions: Some(3),
+            ..Query::default()
+        };
+        assert_eq!(
+            range.invalid_ids(10, &exactly_three).collect::<Vec<_>>(),
+            brute_force(&|_, k| k == 3)
+        );
+
+        let short_patterns = Query {
+            min_repetitions: Some(3),
+            pattern_len: vec![1, 2],
+            ..Query::default()
+        };
+        assert_eq!(
+            range.invalid_ids(10, &short_patterns).collect::<Vec<_>>(),
+            brute_force(&|len, k| k >= 3 && len <= 2)
+        );
+
+        // `111111` is "1" x6, "11" x3, and "111" x2
+        let pattern_len_three = Query {
+            pattern_len: vec![3],
+            ..Query::default()
+        };
+        let ids = range
+            .invalid_ids(10, &pattern_len_three)
+            .collect::<Vec<_>>();
+        assert_eq!(ids, brute_force(&|len, _| len == 3));
+        assert!(ids.contains(&111111));
+
+        let mut stats = Stats::default();
+        for id in ids {
+            stats.insert(id).unwrap();
+        }
+        assert_eq!(stats.count, 900);
+        assert_eq!(stats.min, Some(100100));
+        assert_eq!(stats.max, Some(999999));
+    }
 }
@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day02::{part1, part2};
+use day02::{part1, part2, query, Options, Query};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -22,6 +22,15 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    #[command(flatten)]
+    options: Options,
+
+    /// run a custom query instead of the puzzle parts
+    ///
+    /// when any query constraint is set, part 1 and part 2 are skipped
+    #[command(flatten, next_help_heading = "Query")]
+    query: Query,
 }
 
 impl RunArgs {
@@ -44,11 +53,15 @@ fn main() -> Result<()> {
     let args = RunArgs::parse();
     let input_path = args.input()?;
 
+    if args.query.is_constrained() {
+        return query(&input_path, args.options, &args.query);
+    }
+
     if !args.no_part1 {
-        part1(&input_path)?;
+        part1(&input_path, args.options)?;
     }
     if args.part2 {
-        part2(&input_path)?;
+        part2(&input_path, args.options)?;
     }
     Ok(())
 }
@@ -0,0 +1,60 @@
+//! Report the spread of joltages which each bank can produce.
+
+use crate::{Bank, Joltage};
+use aoclib::parse;
+use color_eyre::{eyre::Context, Result};
+use std::{num::NonZeroUsize, path::Path};
+
+#[derive(Debug, Default, Clone, clap::Args)]
+pub struct Analysis {
+    /// report the least joltage for each bank instead of running the puzzle parts
+    #[arg(long, requires = "digits")]
+    pub minimum: bool,
+
+    /// permit the least joltage to be selected with a leading zero battery
+    #[arg(long, requires = "minimum")]
+    pub allow_leading_zero: bool,
+
+    /// report the nth greatest distinct joltage for each bank instead of running the puzzle parts
+    #[arg(long, value_name = "N", requires = "digits")]
+    pub rank: Option<NonZeroUsize>,
+}
+
+impl Analysis {
+    /// `true` when any analysis has been requested.
+    pub fn is_requested(&self) -> bool {
+        self.minimum || self.rank.is_some()
+    }
+}
+
+fn describe(joltage: Option<Joltage>) -> String {
+    joltage.map_or_else(|| "none".into(), |joltage| joltage.to_string())
+}
+
+/// Print the greatest joltage available from each bank, along with whichever other joltages were requested.
+pub fn analyze(input: &Path, digits: usize, analysis: &Analysis) -> Result<()> {
+    for (n, bank) in parse::<Bank>(input)?.enumerate() {
+        let greatest = bank
+            .select_k_indices(digits)
+            .wrap_err_with(|| format!("selecting batteries for bank {n}"))?;
+        println!("bank {n}:");
+        println!("  greatest: {}", bank.joltage_from_index_slice(&greatest));
+
+        if analysis.minimum {
+            let least = bank
+                .select_k_indices_min(digits, analysis.allow_leading_zero)
+                .map(|indices| bank.joltage_from_index_slice(&indices))
+                .ok();
+            println!("  least: {}", describe(least));
+        }
+
+        if let Some(rank) = analysis.rank {
+            let ranked = bank
+                .select_kth_greatest_indices(digits, rank)
+                .wrap_err_with(|| format!("ranking selections for bank {n}"))?
+                .map(|indices| bank.joltage_from_index_slice(&indices));
+            println!("  rank {rank}: {}", describe(ranked));
+        }
+    }
+    Ok(())
+}
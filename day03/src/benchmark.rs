@@ -0,0 +1,47 @@
+//! Compare battery selection strategies on large, randomly generated banks.
+
+use crate::Bank;
+use color_eyre::{eyre::eyre, Result};
+use std::time::{Duration, Instant};
+use xorshift::XorShift;
+
+/// Generate a bank of `len` batteries with joltages from 1 to 9.
+///
+/// A given seed always produces the same bank.
+pub(crate) fn random_bank(seed: u64, len: usize) -> Bank {
+    let mut rng = XorShift::new(seed);
+    let batteries = (0..len).map(|_| (rng.next_u64() % 9) as u8 + 1).collect();
+    Bank(batteries)
+}
+
+fn time(
+    banks: &[Bank],
+    select: impl Fn(&Bank) -> Result<Vec<usize>>,
+) -> Result<(Duration, Vec<Vec<usize>>)> {
+    let start = Instant::now();
+    let selections = banks.iter().map(select).collect::<Result<Vec<_>>>()?;
+    Ok((start.elapsed(), selections))
+}
+
+/// Time the monotonic-stack selection against the rescanning selection.
+///
+/// Selects `digits` batteries from each of `n_banks` random banks of `bank_len` batteries,
+/// and verifies that both strategies agree.
+pub fn benchmark(n_banks: usize, bank_len: usize, digits: usize) -> Result<()> {
+    let banks = (0..n_banks as u64)
+        .map(|seed| random_bank(seed, bank_len))
+        .collect::<Vec<_>>();
+
+    let (stack_time, stack_selections) = time(&banks, |bank| bank.select_k_indices(digits))?;
+    let (rescan_time, rescan_selections) =
+        time(&banks, |bank| bank.select_k_indices_rescan(digits))?;
+
+    if stack_selections != rescan_selections {
+        return Err(eyre!("selection strategies disagree"));
+    }
+
+    println!("selecting {digits} of {bank_len} batteries in each of {n_banks} banks:");
+    println!("  monotonic stack: {stack_time:?}");
+    println!("  rescan:          {rescan_time:?}");
+    Ok(())
+}
@@ -0,0 +1,111 @@
+//! Show which batteries were selected in each bank, to verify the selection visually.
+
+use crate::{Bank, Joltage};
+use aoclib::parse;
+use color_eyre::{eyre::Context, Result};
+use std::path::Path;
+
+const HIGHLIGHT: &str = "\x1b[1;32m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExplainFormat {
+    /// highlight the selected batteries with ANSI color
+    Color,
+    /// surround the selected batteries with brackets
+    Brackets,
+    /// list the selected indices of each bank as JSON
+    Json,
+}
+
+struct Selection {
+    indices: Vec<usize>,
+    joltage: Joltage,
+}
+
+impl Selection {
+    fn new(bank: &Bank, indices: Vec<usize>) -> Self {
+        let joltage = bank.joltage_from_index_slice(&indices);
+        Self { indices, joltage }
+    }
+
+    /// Render the bank, wrapping each selected battery in the given markers.
+    fn highlight(&self, bank: &Bank, (open, close): (&str, &str)) -> String {
+        let mut selected = self.indices.iter().copied().peekable();
+        bank.0
+            .iter()
+            .enumerate()
+            .map(|(idx, value)| {
+                if selected.next_if_eq(&idx).is_some() {
+                    format!("{open}{value}{close}")
+                } else {
+                    value.to_string()
+                }
+            })
+            .collect()
+    }
+
+    fn json(&self) -> String {
+        let indices = self
+            .indices
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!(
+            r#"{{"indices": [{indices}], "joltage": "{}"}}"#,
+            self.joltage
+        )
+    }
+}
+
+/// Print each bank with the batteries selected for the greatest joltage, along with the runner-up selection.
+pub fn explain(input: &Path, digits: usize, format: ExplainFormat) -> Result<()> {
+    let markers = match format {
+        ExplainFormat::Color => (HIGHLIGHT, RESET),
+        ExplainFormat::Brackets | ExplainFormat::Json => ("[", "]"),
+    };
+    let mut total_output_joltage = Joltage::default();
+    let mut json_banks = Vec::new();
+
+    for (n, bank) in parse::<Bank>(input)?.enumerate() {
+        let best = bank
+            .select_k_indices(digits)
+            .wrap_err_with(|| format!("selecting batteries for bank {n}"))?;
+        let runner_up = bank
+            .select_runner_up_indices(&best)
+            .map(|indices| Selection::new(&bank, indices));
+        let best = Selection::new(&bank, best);
+        total_output_joltage = total_output_joltage + best.joltage.clone();
+
+        if format == ExplainFormat::Json {
+            let runner_up = runner_up.map_or_else(|| "null".into(), |runner_up| runner_up.json());
+            json_banks.push(format!(
+                r#"    {{"bank": {n}, "best": {}, "runner_up": {runner_up}}}"#,
+                best.json()
+            ));
+            continue;
+        }
+
+        println!("bank {n}: {}", best.highlight(&bank, markers));
+        println!("  joltage: {}", best.joltage);
+        match runner_up {
+            Some(runner_up) => println!(
+                "  runner-up: {} ({})",
+                runner_up.joltage,
+                runner_up.highlight(&bank, markers)
+            ),
+            None => println!("  runner-up: none"),
+        }
+    }
+
+    if format == ExplainFormat::Json {
+        println!(
+            "{{\n  \"digits\": {digits},\n  \"total\": \"{total_output_joltage}\",\n  \"banks\": [\n{}\n  ]\n}}",
+            json_banks.join(",\n")
+        );
+    } else {
+        println!("total output joltage ({digits} digits): {total_output_joltage}");
+    }
+    Ok(())
+}
@@ -0,0 +1,136 @@
+//! Joltages which may exceed the capacity of a `u64`.
+//!
+//! Selecting more than 19 batteries can produce a joltage which does not fit in a `u64`, and a total over
+//! many banks can overflow with even fewer. Arithmetic is performed natively for as long as it fits, and
+//! is promoted to a decimal representation when it would otherwise overflow.
+
+use std::{fmt, ops::Add};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Joltage {
+    Native(u64),
+    /// Decimal digits, least significant first, with no leading zeros.
+    ///
+    /// This is only ever used for values which do not fit in a `u64`.
+    Decimal(Vec<u8>),
+}
+
+impl Default for Joltage {
+    fn default() -> Self {
+        Self::Native(0)
+    }
+}
+
+impl Joltage {
+    /// Construct a joltage from its decimal digits, most significant first.
+    pub(crate) fn from_digits(digits: impl IntoIterator<Item = u8>) -> Self {
+        let mut native = Some(0_u64);
+        let mut decimal = Vec::new();
+        for digit in digits {
+            debug_assert!(digit < 10, "decimal digits must be less than 10");
+            native = native.and_then(|value| value.checked_mul(10)?.checked_add(digit.into()));
+            decimal.push(digit);
+        }
+
+        match native {
+            Some(value) => Self::Native(value),
+            None => {
+                decimal.reverse();
+                while decimal.last() == Some(&0) {
+                    decimal.pop();
+                }
+                Self::Decimal(decimal)
+            }
+        }
+    }
+
+    /// Decimal digits, least significant first.
+    fn into_decimal(self) -> Vec<u8> {
+        match self {
+            Self::Decimal(digits) => digits,
+            Self::Native(mut value) => {
+                let mut digits = Vec::new();
+                while value > 0 {
+                    digits.push((value % 10) as u8);
+                    value /= 10;
+                }
+                digits
+            }
+        }
+    }
+}
+
+impl Add for Joltage {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        if let (Self::Native(left), Self::Native(right)) = (&self, &rhs) {
+            if let Some(sum) = left.checked_add(*right) {
+                return Self::Native(sum);
+            }
+        }
+
+        let mut left = self.into_decimal();
+        let right = rhs.into_decimal();
+        if left.len() < right.len() {
+            left.resize(right.len(), 0);
+        }
+        let mut carry = 0;
+        for (idx, digit) in left.iter_mut().enumerate() {
+            let sum = *digit + right.get(idx).copied().unwrap_or_default() + carry;
+            *digit = sum % 10;
+            carry = sum / 10;
+        }
+        if carry > 0 {
+            left.push(carry);
+        }
+        Self::Decimal(left)
+    }
+}
+
+impl fmt::Display for Joltage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Native(value) => write!(f, "{value}"),
+            Self::Decimal(digits) => {
+                for digit in digits.iter().rev() {
+                    write!(f, "{digit}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_values_stay_native() {
+        assert_eq!(
+            Joltage::from_digits([9; 19]),
+            Joltage::Native(9_999_999_999_999_999_999)
+        );
+        assert_eq!(Joltage::from_digits([]), Joltage::Native(0));
+        assert_eq!(Joltage::Native(1) + Joltage::Native(2), Joltage::Native(3));
+    }
+
+    #[test]
+    fn large_values_promote_to_decimal() {
+        let joltage = Joltage::from_digits([9; 25]);
+        assert!(matches!(joltage, Joltage::Decimal(_)));
+        assert_eq!(joltage.to_string(), "9".repeat(25));
+
+        let sum = Joltage::Native(u64::MAX) + Joltage::Native(1);
+        assert_eq!(sum.to_string(), "18446744073709551616");
+
+        let sum = joltage + Joltage::Native(1);
+        assert_eq!(sum.to_string(), format!("1{}", "0".repeat(25)));
+
+        let sum = vec![Joltage::Native(u64::MAX); 3]
+            .into_iter()
+            .fold(Joltage::default(), Add::add);
+        assert_eq!(sum.to_string(), "55340232221128654845");
+    }
+}
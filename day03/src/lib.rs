@@ -1,9 +1,19 @@
+mod analysis;
+mod benchmark;
+mod explain;
+mod joltage;
+
 use aoclib::parse;
 use color_eyre::{
     eyre::{eyre, OptionExt, Report},
     Result,
 };
-use std::{path::Path, str::FromStr};
+use joltage::Joltage;
+use std::{num::NonZeroUsize, path::Path, str::FromStr};
+
+pub use analysis::{analyze, Analysis};
+pub use benchmark::benchmark;
+pub use explain::{explain, ExplainFormat};
 
 /// Battery bank
 struct Bank(Vec<u8>);
@@ -25,50 +35,230 @@ impl FromStr for Bank {
 }
 
 impl Bank {
-    fn joltage_from_indices<const N: usize>(&self, indices: [usize; N]) -> u64 {
+    fn joltage_from_index_slice(&self, indices: &[usize]) -> Joltage {
         debug_assert!(
             indices.windows(2).all(|window| window[0] < window[1]),
             "each subsequent index must increase"
         );
-        indices
+        Joltage::from_digits(indices.iter().map(|&index| self.0[index]))
+    }
+
+    fn joltage_from_indices<const N: usize>(&self, indices: [usize; N]) -> Joltage {
+        self.joltage_from_index_slice(&indices)
+    }
+
+    /// Select the indices of the `k` batteries which together produce the greatest joltage.
+    ///
+    /// This is the "largest subsequence of length `k`" problem, solved in linear time with a monotonic stack:
+    /// each battery displaces any lesser batteries before it, for as long as enough batteries remain to fill
+    /// the selection. Ties are resolved in favor of the earliest index.
+    fn select_k_indices(&self, k: usize) -> Result<Vec<usize>> {
+        if self.0.len() < k {
+            return Err(eyre!("bank has too few batteries"));
+        }
+        Ok(self.select_k_indices_from(0, k))
+    }
+
+    /// Select the indices of the `k` batteries at or after `start` which together produce the greatest joltage.
+    ///
+    /// The caller must ensure that at least `k` batteries are available.
+    fn select_k_indices_from(&self, start: usize, k: usize) -> Vec<usize> {
+        self.select_k_indices_from_by(start, k, |top, value| top < value)
+    }
+
+    /// Select the indices of `k` batteries at or after `start` with a monotonic stack.
+    ///
+    /// `displaces(top, value)` determines whether a battery of `value` displaces the battery of `top` before it.
+    /// The caller must ensure that at least `k` batteries are available.
+    fn select_k_indices_from_by(
+        &self,
+        start: usize,
+        k: usize,
+        displaces: impl Fn(u8, u8) -> bool,
+    ) -> Vec<usize> {
+        let available = &self.0[start..];
+        debug_assert!(available.len() >= k, "enough batteries must be available");
+
+        let mut removable = available.len() - k;
+        let mut stack = Vec::<usize>::with_capacity(available.len());
+        for (idx, &value) in available.iter().enumerate() {
+            let idx = start + idx;
+            while removable > 0
+                && stack
+                    .last()
+                    .is_some_and(|&top| displaces(self.0[top], value))
+            {
+                stack.pop();
+                removable -= 1;
+            }
+            stack.push(idx);
+        }
+        stack.truncate(k);
+
+        stack
+    }
+
+    /// Select the indices of the `k` batteries which together produce the least joltage.
+    ///
+    /// When `allow_leading_zero` is unset, the first selected battery must be nonzero, so that the joltage
+    /// has exactly `k` significant digits. That battery is the least nonzero battery which leaves room for the
+    /// rest of the selection, at its earliest index; the remainder is then the least selection after it.
+    fn select_k_indices_min(&self, k: usize, allow_leading_zero: bool) -> Result<Vec<usize>> {
+        if self.0.len() < k {
+            return Err(eyre!("bank has too few batteries"));
+        }
+        if allow_leading_zero || k == 0 {
+            return Ok(self.select_k_indices_from_by(0, k, |top, value| top > value));
+        }
+
+        // `min_by_key` returns the first minimum, which is the earliest index
+        let (first, _value) = self.0[..=self.0.len() - k]
             .iter()
-            .copied()
-            .rev()
             .enumerate()
-            .map(|(exponent, index)| {
-                let exponent = exponent as u32;
-                let value = self.0[index] as u64;
-                10_u64.pow(exponent) * value
-            })
-            .sum()
+            .filter(|(_idx, value)| **value != 0)
+            .min_by_key(|(_idx, value)| **value)
+            .ok_or_eyre("every battery which could begin the selection is zero")?;
+
+        let mut indices = vec![first];
+        indices.extend(self.select_k_indices_from_by(first + 1, k - 1, |top, value| top > value));
+        Ok(indices)
     }
 
-    fn select_indices<const N: usize>(&self) -> Result<[usize; N]> {
-        if self.0.len() < N {
+    /// Select the indices of the `k` batteries which produce the `rank`th greatest distinct joltage.
+    ///
+    /// Rank 1 is the greatest joltage. Returns `None` if there are fewer than `rank` distinct joltages.
+    ///
+    /// Each distinct joltage corresponds to exactly one selection if every digit is taken at its earliest
+    /// available index. We count the distinct selections of each length from each starting index, then descend
+    /// digit by digit, skipping over entire groups of selections which begin with a greater digit.
+    ///
+    /// This takes `O(k * len)` time and space.
+    fn select_kth_greatest_indices(
+        &self,
+        k: usize,
+        rank: NonZeroUsize,
+    ) -> Result<Option<Vec<usize>>> {
+        let len = self.0.len();
+        if len < k {
             return Err(eyre!("bank has too few batteries"));
         }
 
-        // initialization loop
-        let mut indices = [0; N];
-        for index in 0..N {
-            let mut initial_iter = self.0.iter().enumerate().rev().skip(N - 1 - index);
+        // `next[idx][digit]` is the earliest index at or after `idx` holding `digit`
+        let mut next = vec![[None::<usize>; 10]; len + 1];
+        for (idx, &value) in self.0.iter().enumerate().rev() {
+            next[idx] = next[idx + 1];
+            next[idx][usize::from(value)] = Some(idx);
+        }
+
+        // `distinct[idx * (k + 1) + m]` is the number of distinct selections of `m` batteries at or after `idx`,
+        // saturating: we never need to distinguish counts greater than `rank`
+        let mut distinct = vec![0_u64; (len + 1) * (k + 1)];
+        for idx in (0..=len).rev() {
+            distinct[idx * (k + 1)] = 1;
+            for m in 1..=k {
+                distinct[idx * (k + 1) + m] = next[idx]
+                    .iter()
+                    .flatten()
+                    .map(|&found| distinct[(found + 1) * (k + 1) + m - 1])
+                    .fold(0, u64::saturating_add);
+            }
+        }
+
+        let mut rank = rank.get() as u64;
+        if rank > distinct[k] {
+            return Ok(None);
+        }
+
+        let mut start = 0;
+        let mut indices = Vec::with_capacity(k);
+        for remaining in (1..=k).rev() {
+            let found = next[start]
+                .iter()
+                .rev()
+                .flatten()
+                .copied()
+                .find(|found| {
+                    let count = distinct[(found + 1) * (k + 1) + remaining - 1];
+                    if rank <= count {
+                        return true;
+                    }
+                    rank -= count;
+                    false
+                })
+                .expect("the selections from `start` include the one with this rank");
+            indices.push(found);
+            start = found + 1;
+        }
+
+        Ok(Some(indices))
+    }
+
+    /// Select the indices of the batteries producing the greatest joltage strictly less than that of `best`.
+    ///
+    /// `best` must be the selection produced by [`Self::select_k_indices`]. Returns `None` if every selection
+    /// of this many batteries produces the same joltage.
+    ///
+    /// Any lesser joltage first differs from the best at some position, where it has a lesser digit.
+    /// The later that position, the greater the joltage, so we work backwards from the final position,
+    /// looking for the greatest lesser digit which could fill it, followed by the best possible suffix.
+    fn select_runner_up_indices(&self, best: &[usize]) -> Option<Vec<usize>> {
+        let k = best.len();
+        (0..k).rev().find_map(|position| {
+            let window_start = position
+                .checked_sub(1)
+                .map_or(0, |previous| best[previous] + 1);
+            let window_end = self.0.len() - (k - position);
+            let best_value = self.0[best[position]];
+
+            // `max_by_key` returns the last maximum, so reverse to prefer the earliest index
+            let (idx, _value) = (window_start..=window_end)
+                .map(|idx| (idx, self.0[idx]))
+                .filter(|(_idx, value)| *value < best_value)
+                .rev()
+                .max_by_key(|(_idx, value)| *value)?;
+
+            let mut indices = best[..position].to_vec();
+            indices.push(idx);
+            indices.extend(self.select_k_indices_from(idx + 1, k - position - 1));
+            Some(indices)
+        })
+    }
+
+    /// Select the same indices as [`Self::select_k_indices`] by rescanning the tail of the bank for each position.
+    ///
+    /// This is `O(k * len)`; it is retained as a reference for testing and benchmarking.
+    fn select_k_indices_rescan(&self, k: usize) -> Result<Vec<usize>> {
+        if self.0.len() < k {
+            return Err(eyre!("bank has too few batteries"));
+        }
+
+        let mut indices = Vec::with_capacity(k);
+        for index in 0..k {
+            let mut initial_iter = self.0.iter().enumerate().rev().skip(k - 1 - index);
             let mut adapted_iter;
-            let iter = if index > 0 {
-                let previous_index = indices[index - 1];
+            let iter = if let Some(&previous_index) = indices.last() {
                 adapted_iter = initial_iter.take_while(move |(idx, _value)| *idx > previous_index);
                 &mut adapted_iter as &mut dyn Iterator<Item = _>
             } else {
                 &mut initial_iter
             };
 
-            indices[index] = iter
-                .max_by_key(|(_idx, value)| **value)
-                .expect("maxing a non-empty list always produces something")
-                .0;
+            indices.push(
+                iter.max_by_key(|(_idx, value)| **value)
+                    .expect("maxing a non-empty list always produces something")
+                    .0,
+            );
         }
 
         Ok(indices)
     }
+
+    fn select_indices<const N: usize>(&self) -> Result<[usize; N]> {
+        let indices = self.select_k_indices(N)?;
+        Ok(indices
+            .try_into()
+            .expect("select_k_indices produces exactly the requested number of indices"))
+    }
 }
 
 fn solve<const N: usize>(input: &Path, part_n: u8) -> Result<()> {
@@ -77,11 +267,27 @@ fn solve<const N: usize>(input: &Path, part_n: u8) -> Result<()> {
             bank.select_indices::<N>()
                 .map(|indices| bank.joltage_from_indices(indices))
         })
-        .try_fold(0, |acc, elem| -> Result<_> { Ok(elem? + acc) })?;
+        .try_fold(Joltage::default(), |acc, elem| -> Result<_> {
+            Ok(acc + elem?)
+        })?;
     println!("total output joltage (part {part_n}): {total_output_joltage}");
     Ok(())
 }
 
+/// Compute the total output joltage when selecting `digits` batteries from each bank.
+pub fn solve_digits(input: &Path, digits: usize) -> Result<()> {
+    let total_output_joltage = parse::<Bank>(input)?
+        .map(|bank| -> Result<_> {
+            bank.select_k_indices(digits)
+                .map(|indices| bank.joltage_from_index_slice(&indices))
+        })
+        .try_fold(Joltage::default(), |acc, elem| -> Result<_> {
+            Ok(acc + elem?)
+        })?;
+    println!("total output joltage ({digits} digits): {total_output_joltage}");
+    Ok(())
+}
+
 pub fn part1(input: &Path) -> Result<()> {
     solve::<2>(input, 1)
 }
@@ -89,3 +295,130 @@ pub fn part1(input: &Path) -> Result<()> {
 pub fn part2(input: &Path) -> Result<()> {
     solve::<12>(input, 2)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monotonic_stack_matches_rescan() {
+        for (seed, len) in [(1, 1), (2, 15), (3, 100), (4, 1000)] {
+            let bank = benchmark::random_bank(seed, len);
+            for k in [0, 1, 2, len / 2, len.saturating_sub(1), len] {
+                if k > len {
+                    continue;
+                }
+                assert_eq!(
+                    bank.select_k_indices(k).unwrap(),
+                    bank.select_k_indices_rescan(k).unwrap(),
+                    "seed {seed} len {len} k {k}"
+                );
+            }
+        }
+    }
+
+    /// Every selection of `k` batteries, as its digits.
+    fn all_selections(bank: &Bank, k: usize) -> impl Iterator<Item = Vec<u8>> + '_ {
+        let len = bank.0.len();
+        (0_u32..1 << len)
+            .filter(move |mask| mask.count_ones() as usize == k)
+            .map(move |mask| {
+                (0..len)
+                    .filter(|idx| mask & 1 << idx != 0)
+                    .map(|idx| bank.0[idx])
+                    .collect()
+            })
+    }
+
+    fn joltage_value(digits: &[u8]) -> u64 {
+        digits
+            .iter()
+            .fold(0, |acc, &digit| acc * 10 + u64::from(digit))
+    }
+
+    /// Every distinct joltage for `k` batteries, in descending order.
+    fn all_joltages(bank: &Bank, k: usize) -> Vec<u64> {
+        let mut joltages = all_selections(bank, k)
+            .map(|digits| joltage_value(&digits))
+            .collect::<Vec<_>>();
+        joltages.sort_unstable_by(|a, b| b.cmp(a));
+        joltages.dedup();
+        joltages
+    }
+
+    fn test_banks() -> impl Iterator<Item = Bank> {
+        [
+            "987654321111111",
+            "811111111111119",
+            "234234234234278",
+            "1111",
+            "1212",
+            "1020304050",
+            "0000",
+            "00100",
+        ]
+        .into_iter()
+        .map(|bank| bank.parse::<Bank>().unwrap())
+        .chain((0..20).map(|seed| benchmark::random_bank(seed, 12)))
+    }
+
+    #[test]
+    fn runner_up_is_second_greatest_joltage() {
+        for bank in test_banks() {
+            for k in 1..=bank.0.len().min(4) {
+                let best = bank.select_k_indices(k).unwrap();
+                let runner_up = bank
+                    .select_runner_up_indices(&best)
+                    .map(|indices| bank.joltage_from_index_slice(&indices));
+                let expect = all_joltages(&bank, k).get(1).copied().map(Joltage::Native);
+                assert_eq!(runner_up, expect, "bank {:?} k {k}", bank.0);
+            }
+        }
+    }
+
+    #[test]
+    fn least_joltage_matches_brute_force() {
+        for bank in test_banks() {
+            for k in 0..=bank.0.len().min(4) {
+                let least = bank
+                    .select_k_indices_min(k, true)
+                    .map(|indices| bank.joltage_from_index_slice(&indices))
+                    .unwrap();
+                let expect = all_joltages(&bank, k).last().copied().map(Joltage::Native);
+                assert_eq!(Some(least), expect, "bank {:?} k {k}", bank.0);
+
+                let least = bank
+                    .select_k_indices_min(k, false)
+                    .ok()
+                    .map(|indices| bank.joltage_from_index_slice(&indices));
+                let expect = all_selections(&bank, k)
+                    .filter(|digits| digits.first() != Some(&0))
+                    .map(|digits| joltage_value(&digits))
+                    .min()
+                    .map(Joltage::Native);
+                assert_eq!(
+                    least, expect,
+                    "bank {:?} k {k} without leading zero",
+                    bank.0
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn kth_greatest_matches_brute_force() {
+        for bank in test_banks() {
+            for k in 0..=bank.0.len().min(4) {
+                let joltages = all_joltages(&bank, k);
+                for rank in 1..=joltages.len() + 1 {
+                    let ranked = bank
+                        .select_kth_greatest_indices(k, NonZeroUsize::new(rank).unwrap())
+                        .unwrap()
+                        .map(|indices| bank.joltage_from_index_slice(&indices));
+                    let expect = joltages.get(rank - 1).copied().map(Joltage::Native);
+                    assert_eq!(ranked, expect, "bank {:?} k {k} rank {rank}", bank.0);
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day03::{part1, part2};
+use day03::{analyze, benchmark, explain, part1, part2, solve_digits, Analysis, ExplainFormat};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -22,6 +22,27 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    /// select this many batteries per bank instead of running the puzzle parts
+    #[arg(long)]
+    digits: Option<usize>,
+
+    /// show the selected batteries and the runner-up selection for each bank instead of running the puzzle parts
+    #[arg(long, value_name = "FORMAT", requires = "digits")]
+    explain: Option<ExplainFormat>,
+
+    #[command(flatten)]
+    analysis: Analysis,
+
+    /// benchmark battery selection on random banks of this many batteries instead of running the puzzle parts
+    ///
+    /// selects `--digits` batteries per bank, or 12 if unset
+    #[arg(long, value_name = "BANK_LEN")]
+    benchmark: Option<usize>,
+
+    /// number of random banks to generate when benchmarking
+    #[arg(long, default_value_t = 10, requires = "benchmark")]
+    benchmark_banks: usize,
 }
 
 impl RunArgs {
@@ -42,8 +63,26 @@ impl RunArgs {
 fn main() -> Result<()> {
     color_eyre::install()?;
     let args = RunArgs::parse();
+    if let Some(bank_len) = args.benchmark {
+        return benchmark(args.benchmark_banks, bank_len, args.digits.unwrap_or(12));
+    }
+
     let input_path = args.input()?;
 
+    if let Some(format) = args.explain {
+        let digits = args.digits.expect("clap requires digits when explaining");
+        return explain(&input_path, digits, format);
+    }
+
+    if args.analysis.is_requested() {
+        let digits = args.digits.expect("clap requires digits when analyzing");
+        return analyze(&input_path, digits, &args.analysis);
+    }
+
+    if let Some(digits) = args.digits {
+        return solve_digits(&input_path, digits);
+    }
+
     if !args.no_part1 {
         part1(&input_path)?;
     }
@@ -0,0 +1,220 @@
+//! A bit-packed grid of paper rolls, with word-parallel neighbour counting.
+//!
+//! Each row is packed into `u64` words, one bit per cell, with unused bits at the end of each row kept clear.
+//! To count neighbours, we shift the words of the rows above, at, and below each word so that every neighbour
+//! lines up with the cell it surrounds, then sum those shifted words with a bit-sliced adder: `planes[n]` holds
+//! bit `n` of the count for each of the 64 cells at once. Comparing the counts against the threshold is then
+//! a handful of bitwise operations per word.
+
+use crate::{rule::Accessibility, waves::rows, Tile};
+use aoclib::geometry::Map;
+use color_eyre::{eyre::eyre, Result};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Enough bit planes to count up to 15 neighbours.
+const PLANES: usize = 4;
+
+pub(crate) struct BitGrid {
+    words_per_row: usize,
+    height: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    /// Ensure that the accessibility rule can be evaluated on a bit-packed grid.
+    ///
+    /// Neighbours must be within one cell in each direction, and there may be no more than 15 of them.
+    /// Wrapping is not supported.
+    pub(crate) fn supports(accessibility: &Accessibility) -> Result<()> {
+        if accessibility.wrap {
+            return Err(eyre!("the bitset backend does not support wrapping"));
+        }
+        if accessibility.offsets.len() >= 1 << PLANES {
+            return Err(eyre!(
+                "the bitset backend supports at most {} neighbours",
+                (1 << PLANES) - 1
+            ));
+        }
+        if let Some(offset) = accessibility
+            .offsets
+            .iter()
+            .find(|offset| offset.dx.abs() > 1 || offset.dy.abs() > 1)
+        {
+            return Err(eyre!(
+                "the bitset backend only supports adjacent neighbours, not {offset}"
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn new(map: &Map<Tile>) -> Self {
+        let words_per_row = map.width().div_ceil(WORD_BITS);
+        let height = map.height();
+        let mut words = vec![0; words_per_row * height];
+        for (row, points) in rows(map).enumerate() {
+            for (column, point) in points.enumerate() {
+                if map[point] == Tile::PaperRoll {
+                    words[row * words_per_row + column / WORD_BITS] |= 1 << (column % WORD_BITS);
+                }
+            }
+        }
+        Self {
+            words_per_row,
+            height,
+            words,
+        }
+    }
+
+    /// The word at `(row, word)`, or 0 if that is out of bounds.
+    fn word(&self, row: isize, word: isize) -> u64 {
+        if row < 0 || row as usize >= self.height || word < 0 || word as usize >= self.words_per_row
+        {
+            return 0;
+        }
+        self.words[row as usize * self.words_per_row + word as usize]
+    }
+
+    /// For each cell in `(row, word)`, the cell displaced from it by `(dx, dy)`.
+    ///
+    /// Rows are stored from the top, so a positive `dy` refers to the row before.
+    fn displaced(&self, row: usize, word: usize, dx: i32, dy: i32) -> u64 {
+        let row = row as isize - dy as isize;
+        let word = word as isize;
+        match dx {
+            0 => self.word(row, word),
+            // the cell to the right is the next bit up, which may be in the next word
+            1 => self.word(row, word) >> 1 | self.word(row, word + 1) << (WORD_BITS - 1),
+            -1 => self.word(row, word) << 1 | self.word(row, word - 1) >> (WORD_BITS - 1),
+            _ => unreachable!("supported rules only have adjacent neighbours"),
+        }
+    }
+
+    /// Write the cells of each word of `row` which are accessible paper rolls to `accessible`.
+    fn accessible_in_row(&self, row: usize, accessibility: &Accessibility, accessible: &mut [u64]) {
+        for (word, accessible) in accessible.iter_mut().enumerate() {
+            let mut planes = [0; PLANES];
+            for offset in &accessibility.offsets {
+                add(&mut planes, self.displaced(row, word, offset.dx, offset.dy));
+            }
+            *accessible = self.words[row * self.words_per_row + word]
+                & less_than(&planes, accessibility.threshold);
+        }
+    }
+
+    pub(crate) fn count_accessible(&self, accessibility: &Accessibility) -> u32 {
+        let mut accessible = vec![0; self.words_per_row];
+        (0..self.height)
+            .map(|row| {
+                self.accessible_in_row(row, accessibility, &mut accessible);
+                accessible.iter().map(|word| word.count_ones()).sum::<u32>()
+            })
+            .sum()
+    }
+
+    /// As [`crate::remove_all_by_rescan`], for the rules accepted by [`Self::supports`]: neighbours within
+    /// one cell in each direction, at most 15 of them, and no wrapping.
+    ///
+    /// A roll can only become accessible when a neighbour is removed, so after the first round
+    /// we only re-examine the rows surrounding those in which rolls were removed.
+    pub(crate) fn remove_all(mut self, accessibility: &Accessibility) -> u32 {
+        let mut accessible = vec![0; self.words.len()];
+        let mut candidate_rows = (0..self.height).collect::<Vec<_>>();
+        let mut total_removed = 0;
+        while !candidate_rows.is_empty() {
+            for &row in &candidate_rows {
+                let range = row * self.words_per_row..(row + 1) * self.words_per_row;
+                self.accessible_in_row(row, accessibility, &mut accessible[range]);
+            }
+
+            let mut next_rows = Vec::new();
+            for row in candidate_rows {
+                let range = row * self.words_per_row..(row + 1) * self.words_per_row;
+                let removed = accessible[range.clone()]
+                    .iter()
+                    .map(|word| word.count_ones())
+                    .sum::<u32>();
+                if removed == 0 {
+                    continue;
+                }
+                total_removed += removed;
+                for (word, accessible) in
+                    self.words[range.clone()].iter_mut().zip(&accessible[range])
+                {
+                    *word &= !accessible;
+                }
+                next_rows.extend(row.saturating_sub(1)..(row + 2).min(self.height));
+            }
+            next_rows.sort_unstable();
+            next_rows.dedup();
+            candidate_rows = next_rows;
+        }
+        total_removed
+    }
+}
+
+/// Add a 1-bit value to each bit-sliced count.
+fn add(planes: &mut [u64; PLANES], mut carry: u64) {
+    for plane in planes {
+        let sum = *plane ^ carry;
+        carry &= *plane;
+        *plane = sum;
+    }
+}
+
+/// The cells whose bit-sliced count is exactly `value`.
+fn equal_to(planes: &[u64; PLANES], value: usize) -> u64 {
+    planes.iter().enumerate().fold(!0, |acc, (bit, plane)| {
+        acc & if value >> bit & 1 == 1 {
+            *plane
+        } else {
+            !plane
+        }
+    })
+}
+
+/// The cells whose bit-sliced count is less than `threshold`.
+fn less_than(planes: &[u64; PLANES], threshold: usize) -> u64 {
+    (0..threshold.min(1 << PLANES)).fold(0, |acc, value| acc | equal_to(planes, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{random_map, remove_all_by_rescan, test_rules};
+
+    #[test]
+    fn matches_map() {
+        let rules = test_rules()
+            .into_iter()
+            .map(|rule| Accessibility::from(&rule))
+            .filter(|accessibility| BitGrid::supports(accessibility).is_ok())
+            .collect::<Vec<_>>();
+        assert!(!rules.is_empty());
+
+        for accessibility in &rules {
+            for (seed, width) in (1..).zip([1, 5, 63, 64, 65, 130]) {
+                for density in [64, 192, 255] {
+                    let map = random_map(seed, width, 11, density);
+                    let grid = BitGrid::new(&map);
+                    let accessible = map
+                        .iter()
+                        .filter(|(point, _tile)| {
+                            accessibility.is_accessable_by_forklift(&map, *point)
+                        })
+                        .count();
+                    assert_eq!(
+                        grid.count_accessible(accessibility) as usize,
+                        accessible,
+                        "width {width} density {density} rule {accessibility:?}"
+                    );
+                    assert_eq!(
+                        grid.remove_all(accessibility),
+                        remove_all_by_rescan(map, accessibility),
+                        "width {width} density {density} rule {accessibility:?}"
+                    );
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,24 @@
-use aoclib::geometry::{point::PointTrait, tile::DisplayWidth, Map, Point};
+mod bitgrid;
+mod render;
+mod rule;
+mod waves;
+mod worklist;
+
+use aoclib::geometry::{tile::DisplayWidth, Map};
+use bitgrid::BitGrid;
 use color_eyre::Result;
+use rule::Accessibility;
 use std::path::Path;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::Display, parse_display::FromStr)]
+pub use render::{render, RenderMode};
+pub use rule::{Neighbourhood, Offset, Rule};
+pub use waves::{waves, WaveFormat};
+
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, parse_display::Display, parse_display::FromStr,
+)]
 enum Tile {
+    #[default]
     #[display(".")]
     Empty,
     #[display("@")]
@@ -14,8 +29,7 @@ impl DisplayWidth for Tile {
     const DISPLAY_WIDTH: usize = 1;
 }
 
-// this code goes live if we uncomment the debug map section of pt1
-#[allow(dead_code)]
+/// A tile annotated with its state in some round of removal.
 #[derive(
     Debug, Default, Clone, Copy, PartialEq, Eq, parse_display::Display, parse_display::FromStr,
 )]
@@ -27,26 +41,42 @@ enum DebugTile {
     PaperRoll,
     #[display("x")]
     Accessable,
+    #[display("-")]
+    Removed,
 }
 
 impl DisplayWidth for DebugTile {
     const DISPLAY_WIDTH: usize = 1;
 }
 
-fn is_accessable_by_forklift(map: &Map<Tile>, point: Point) -> bool {
-    map.in_bounds(point)
-        && map[point] == Tile::PaperRoll
-        && point
-            .adjacent()
-            .filter(|adj| map.in_bounds(*adj) && map[*adj] == Tile::PaperRoll)
-            .count()
-            < 4
+/// How to store the map of paper rolls.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Backend {
+    /// one tile per cell
+    #[default]
+    Map,
+    /// one bit per cell, counting neighbours for many cells at once
+    Bitset,
 }
 
-fn remove_accessable(map: &Map<Tile>, next_map: &mut Map<Tile>) -> u32 {
+/// How to simulate the repeated removal of accessible paper rolls.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Strategy {
+    /// track the neighbour count of each roll, re-examining only the neighbours of removed rolls
+    #[default]
+    Worklist,
+    /// rescan and clone the whole map each round
+    Rescan,
+}
+
+fn remove_accessable(
+    map: &Map<Tile>,
+    next_map: &mut Map<Tile>,
+    accessibility: &Accessibility,
+) -> u32 {
     let mut removed = 0;
     for (point, &tile) in map.iter() {
-        if tile == Tile::PaperRoll && is_accessable_by_forklift(map, point) {
+        if tile == Tile::PaperRoll && accessibility.is_accessable_by_forklift(map, point) {
             removed += 1;
             next_map[point] = Tile::Empty;
         }
@@ -54,40 +84,104 @@ fn remove_accessable(map: &Map<Tile>, next_map: &mut Map<Tile>) -> u32 {
     removed
 }
 
-pub fn part1(input: &Path) -> Result<()> {
+pub fn part1(input: &Path, rule: &Rule, backend: Backend) -> Result<()> {
     let map = <Map<Tile> as TryFrom<&Path>>::try_from(input)?;
-    // let mut debug_map = Map::<DebugTile>::new(map.width(), map.height());
-    // for (point, tile) in map.iter() {
-    //     if *tile == Tile::PaperRoll {
-    //         if is_accessable_by_forklift(&map, point) {
-    //             debug_map[point] = DebugTile::Accessable;
-    //         } else {
-    //             debug_map[point] = DebugTile::PaperRoll;
-    //         }
-    //     }
-    // }
-    // eprintln!("{debug_map}");
-    let accessable_by_forklift = map
-        .iter()
-        .filter(|(point, _tile)| is_accessable_by_forklift(&map, *point))
-        .count();
+    let accessibility = Accessibility::from(rule);
+    let accessable_by_forklift = match backend {
+        Backend::Map => map
+            .iter()
+            .filter(|(point, _tile)| accessibility.is_accessable_by_forklift(&map, *point))
+            .count(),
+        Backend::Bitset => {
+            BitGrid::supports(&accessibility)?;
+            BitGrid::new(&map).count_accessible(&accessibility) as usize
+        }
+    };
     println!("points accessable by forklift: {accessable_by_forklift}");
     Ok(())
 }
 
-pub fn part2(input: &Path) -> Result<()> {
-    let mut map = <Map<Tile> as TryFrom<&Path>>::try_from(input)?;
+/// Remove accessible paper rolls round by round until none remain, returning the total removed.
+fn remove_all_by_rescan(mut map: Map<Tile>, accessibility: &Accessibility) -> u32 {
     let mut next_map = map.clone();
 
     let mut total_removed = 0;
     while {
-        let removed = remove_accessable(&map, &mut next_map);
+        let removed = remove_accessable(&map, &mut next_map, accessibility);
         total_removed += removed;
         removed > 0
     } {
         map = next_map.clone();
     }
+    total_removed
+}
 
+/// Solve part 2; the strategy only applies to the map backend.
+pub fn part2(input: &Path, strategy: Strategy, rule: &Rule, backend: Backend) -> Result<()> {
+    let map = <Map<Tile> as TryFrom<&Path>>::try_from(input)?;
+    let accessibility = Accessibility::from(rule);
+    let total_removed = match (backend, strategy) {
+        (Backend::Map, Strategy::Worklist) => worklist::remove_all(map, &accessibility),
+        (Backend::Map, Strategy::Rescan) => remove_all_by_rescan(map, &accessibility),
+        (Backend::Bitset, _) => {
+            BitGrid::supports(&accessibility)?;
+            BitGrid::new(&map).remove_all(&accessibility)
+        }
+    };
     println!("total removed (pt2): {total_removed}");
     Ok(())
 }
+
+/// A map with paper rolls placed pseudo-randomly at roughly the given density, out of 256.
+#[cfg(test)]
+pub(crate) fn random_map(seed: u64, width: usize, height: usize, density: u8) -> Map<Tile> {
+    let mut rng = xorshift::XorShift::new(seed);
+    let mut map = Map::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            if (rng.next_u64() as u8) < density {
+                map[aoclib::geometry::Point::new(x as i32, y as i32)] = Tile::PaperRoll;
+            }
+        }
+    }
+    map
+}
+
+/// A variety of rules, including asymmetric neighbourhoods and wrapping, to exercise the simulations.
+#[cfg(test)]
+pub(crate) fn test_rules() -> Vec<Rule> {
+    let custom = [(1, 0), (0, 2), (-1, -1), (3, 1)]
+        .into_iter()
+        .map(|(dx, dy)| Offset { dx, dy })
+        .collect::<Vec<_>>();
+    let adjacent_custom = [(1, 0), (1, 1), (0, -1)]
+        .into_iter()
+        .map(|(dx, dy)| Offset { dx, dy })
+        .collect::<Vec<_>>();
+    let mut rules = Vec::new();
+    for wrap in [false, true] {
+        for neighbourhood in [Neighbourhood::Moore, Neighbourhood::VonNeumann] {
+            for threshold in [0, 2, 4, 9] {
+                rules.push(Rule {
+                    neighbourhood,
+                    offset: Vec::new(),
+                    threshold,
+                    wrap,
+                });
+            }
+        }
+        rules.push(Rule {
+            offset: custom.clone(),
+            threshold: 2,
+            wrap,
+            ..Rule::default()
+        });
+        rules.push(Rule {
+            offset: adjacent_custom.clone(),
+            threshold: 2,
+            wrap,
+            ..Rule::default()
+        });
+    }
+    rules
+}
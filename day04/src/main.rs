@@ -1,9 +1,9 @@
 use aoclib::{config::Config, website::get_input};
-use day04::{part1, part2};
+use day04::{part1, part2, render, waves, Backend, RenderMode, Rule, Strategy, WaveFormat};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 const YEAR: u32 = 2025;
 const DAY: u8 = 4;
@@ -22,6 +22,37 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    /// how to store the map of paper rolls
+    #[arg(long, value_enum, default_value_t)]
+    backend: Backend,
+
+    /// how to simulate the removal of paper rolls in part 2 with the map backend
+    #[arg(long, value_enum, default_value_t)]
+    strategy: Strategy,
+
+    #[command(flatten)]
+    rule: Rule,
+
+    /// export the round in which each paper roll is removed instead of running the puzzle parts
+    #[arg(long, value_name = "FORMAT")]
+    waves: Option<WaveFormat>,
+
+    /// write the exported rounds to this file instead of stdout
+    #[arg(long, value_name = "PATH", requires = "waves")]
+    waves_output: Option<PathBuf>,
+
+    /// render one frame per round of removal instead of running the puzzle parts
+    #[arg(long, value_name = "MODE")]
+    render: Option<RenderMode>,
+
+    /// milliseconds to pause between ASCII frames
+    #[arg(long, default_value_t = 250, requires = "render")]
+    render_delay: u64,
+
+    /// directory in which to write image frames
+    #[arg(long, default_value = "frames", requires = "render")]
+    render_dir: PathBuf,
 }
 
 impl RunArgs {
@@ -44,11 +75,30 @@ fn main() -> Result<()> {
     let args = RunArgs::parse();
     let input_path = args.input()?;
 
+    if let Some(mode) = args.render {
+        return render(
+            &input_path,
+            &args.rule,
+            mode,
+            Duration::from_millis(args.render_delay),
+            &args.render_dir,
+        );
+    }
+
+    if let Some(format) = args.waves {
+        return waves(
+            &input_path,
+            format,
+            args.waves_output.as_deref(),
+            &args.rule,
+        );
+    }
+
     if !args.no_part1 {
-        part1(&input_path)?;
+        part1(&input_path, &args.rule, args.backend)?;
     }
     if args.part2 {
-        part2(&input_path)?;
+        part2(&input_path, args.strategy, &args.rule, args.backend)?;
     }
     Ok(())
 }
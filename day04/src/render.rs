@@ -0,0 +1,209 @@
+//! Render each round of paper roll removal as a frame.
+//!
+//! Each frame shows the map at the start of a round: rolls which are accessible in that round are marked,
+//! as are rolls which were removed in earlier rounds. A final frame shows the rolls which are never removed.
+
+use crate::{
+    rule::{Accessibility, Rule},
+    waves::{last_round, removal_waves, rows, Removal},
+    DebugTile, Tile,
+};
+use aoclib::geometry::Map;
+use color_eyre::{eyre::Context, Result};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+/// Each tile is drawn as a square of this many pixels on a side in image frames.
+const PIXELS_PER_TILE: usize = 4;
+
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum RenderMode {
+    /// print each frame to the terminal, pausing between them
+    Ascii,
+    /// write each frame to a numbered PPM image
+    Ppm,
+}
+
+impl DebugTile {
+    fn color(self) -> [u8; 3] {
+        match self {
+            Self::Empty => [0x20, 0x20, 0x20],
+            Self::PaperRoll => [0xf0, 0xf0, 0xe0],
+            Self::Accessable => [0xe0, 0x30, 0x30],
+            Self::Removed => [0x60, 0x50, 0x40],
+        }
+    }
+}
+
+/// The state of each tile at the start of `round`.
+fn frame(waves: &Map<Removal>, round: u32) -> Map<DebugTile> {
+    let mut frame = Map::<DebugTile>::new(waves.width(), waves.height());
+    for (point, removal) in waves.iter() {
+        frame[point] = match *removal {
+            Removal::Empty => DebugTile::Empty,
+            Removal::Never => DebugTile::PaperRoll,
+            Removal::Round(removed) if removed < round => DebugTile::Removed,
+            Removal::Round(removed) if removed == round => DebugTile::Accessable,
+            Removal::Round(_) => DebugTile::PaperRoll,
+        };
+    }
+    frame
+}
+
+fn write_ppm(frame: &Map<DebugTile>, mut out: impl Write) -> Result<()> {
+    writeln!(
+        out,
+        "P6\n{} {}\n255",
+        frame.width() * PIXELS_PER_TILE,
+        frame.height() * PIXELS_PER_TILE
+    )?;
+    for row in rows(frame) {
+        let mut pixels = Vec::with_capacity(frame.width() * PIXELS_PER_TILE * 3);
+        for point in row {
+            for _ in 0..PIXELS_PER_TILE {
+                pixels.extend(frame[point].color());
+            }
+        }
+        for _ in 0..PIXELS_PER_TILE {
+            out.write_all(&pixels)?;
+        }
+    }
+    Ok(())
+}
+
+/// Render one frame per round of removal.
+///
+/// ASCII frames are printed to the terminal with `delay` between them; image frames are written to `dir`.
+pub fn render(
+    input: &Path,
+    rule: &Rule,
+    mode: RenderMode,
+    delay: Duration,
+    dir: &Path,
+) -> Result<()> {
+    let map = <Map<Tile> as TryFrom<&Path>>::try_from(input)?;
+    let waves = removal_waves(map, &Accessibility::from(rule));
+    let n_frames = last_round(&waves) + 1;
+
+    if mode == RenderMode::Ppm {
+        fs::create_dir_all(dir).wrap_err_with(|| format!("creating {}", dir.display()))?;
+    }
+
+    for round in 1..=n_frames {
+        let frame = frame(&waves, round);
+        match mode {
+            RenderMode::Ascii => {
+                if round > 1 {
+                    thread::sleep(delay);
+                }
+                let accessible = frame
+                    .iter()
+                    .filter(|(_point, tile)| **tile == DebugTile::Accessable)
+                    .count();
+                print!("{CLEAR_SCREEN}");
+                println!("round {round}/{n_frames}: {accessible} accessible");
+                println!("{frame}");
+            }
+            RenderMode::Ppm => {
+                let path = dir.join(format!("round-{round:04}.ppm"));
+                let file =
+                    File::create(&path).wrap_err_with(|| format!("creating {}", path.display()))?;
+                let mut out = BufWriter::new(file);
+                write_ppm(&frame, &mut out)?;
+                out.flush()?;
+            }
+        }
+    }
+
+    if mode == RenderMode::Ppm {
+        println!("wrote {n_frames} frames to {}", dir.display());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aoclib::geometry::{Direction, Point};
+
+    #[test]
+    fn frames_mark_each_round() {
+        let mut waves = Map::<Removal>::new(3, 2);
+        let removals = [
+            Removal::Empty,
+            Removal::Never,
+            Removal::Round(1),
+            Removal::Round(2),
+            Removal::Round(3),
+            Removal::Round(3),
+        ];
+        let points = (0..2)
+            .flat_map(|y| (0..3).map(move |x| Point::new(x, y)))
+            .collect::<Vec<_>>();
+        for (&point, removal) in points.iter().zip(removals) {
+            waves[point] = removal;
+        }
+        let tiles = |round| {
+            let frame = frame(&waves, round);
+            points.iter().map(|&point| frame[point]).collect::<Vec<_>>()
+        };
+
+        use DebugTile::*;
+        assert_eq!(
+            tiles(0),
+            [Empty, PaperRoll, PaperRoll, PaperRoll, PaperRoll, PaperRoll]
+        );
+        assert_eq!(
+            tiles(2),
+            [Empty, PaperRoll, Removed, Accessable, PaperRoll, PaperRoll]
+        );
+        assert_eq!(
+            tiles(3),
+            [Empty, PaperRoll, Removed, Removed, Accessable, Accessable]
+        );
+        // the frame after the last round shows only the rolls which are never removed
+        assert_eq!(
+            tiles(4),
+            [Empty, PaperRoll, Removed, Removed, Removed, Removed]
+        );
+    }
+
+    #[test]
+    fn ppm_scales_each_tile() {
+        let mut frame = Map::<DebugTile>::new(2, 2);
+        let top_left = frame.top_left();
+        let (dx, dy) = Direction::Down.deltas();
+        let bottom_left = Point::new(top_left.x + dx, top_left.y + dy);
+        let right = |point: Point| Point::new(point.x + 1, point.y);
+        frame[top_left] = DebugTile::Empty;
+        frame[right(top_left)] = DebugTile::Accessable;
+        frame[bottom_left] = DebugTile::PaperRoll;
+        frame[right(bottom_left)] = DebugTile::Removed;
+
+        let mut out = Vec::new();
+        write_ppm(&frame, &mut out).unwrap();
+
+        let mut expected = b"P6\n8 8\n255\n".to_vec();
+        for (left, right) in [
+            (DebugTile::Empty, DebugTile::Accessable),
+            (DebugTile::PaperRoll, DebugTile::Removed),
+        ] {
+            let row = [left.color(); PIXELS_PER_TILE]
+                .into_iter()
+                .chain([right.color(); PIXELS_PER_TILE])
+                .flatten()
+                .collect::<Vec<_>>();
+            for _ in 0..PIXELS_PER_TILE {
+                expected.extend(&row);
+            }
+        }
+        assert_eq!(out, expected);
+    }
+}
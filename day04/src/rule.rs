@@ -0,0 +1,150 @@
+//! Which paper rolls a forklift can reach.
+//!
+//! The puzzle's rule is that a roll is accessible when fewer than 4 of its 8 surrounding cells hold rolls.
+//! Both the set of surrounding cells and the threshold can be varied, as can whether the map wraps around
+//! at its edges.
+
+use crate::Tile;
+use aoclib::geometry::{Map, Point};
+
+/// A set of surrounding cells.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Neighbourhood {
+    /// the 8 cells which share an edge or a corner
+    #[default]
+    Moore,
+    /// the 4 cells which share an edge
+    VonNeumann,
+}
+
+impl Neighbourhood {
+    fn offsets(self) -> Vec<Offset> {
+        let orthogonal = |dx: i32, dy: i32| dx == 0 || dy == 0;
+        (-1..=1)
+            .flat_map(|dy| (-1..=1).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| (dx, dy) != (0, 0))
+            .filter(|&(dx, dy)| self == Self::Moore || orthogonal(dx, dy))
+            .map(|(dx, dy)| Offset { dx, dy })
+            .collect()
+    }
+}
+
+/// The displacement of a surrounding cell; `dy` is positive upwards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::FromStr, parse_display::Display)]
+#[display("{dx},{dy}")]
+pub struct Offset {
+    pub dx: i32,
+    pub dy: i32,
+}
+
+/// The rule determining which paper rolls a forklift can reach.
+#[derive(Debug, Clone, clap::Args)]
+pub struct Rule {
+    /// cells surrounding a paper roll which may crowd it
+    #[arg(long, value_enum, default_value_t)]
+    pub neighbourhood: Neighbourhood,
+
+    /// use a custom neighbourhood including a cell at this offset `DX,DY`; may be repeated
+    #[arg(
+        long,
+        value_name = "DX,DY",
+        allow_hyphen_values = true,
+        conflicts_with = "neighbourhood"
+    )]
+    pub offset: Vec<Offset>,
+
+    /// a paper roll with at least this many surrounding paper rolls cannot be reached by a forklift
+    #[arg(long, default_value_t = 4)]
+    pub threshold: usize,
+
+    /// wrap the map around at its edges, so the leftmost column surrounds the rightmost, and so on
+    #[arg(long)]
+    pub wrap: bool,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            neighbourhood: Neighbourhood::default(),
+            offset: Vec::new(),
+            threshold: 4,
+            wrap: false,
+        }
+    }
+}
+
+/// A [`Rule`] prepared for evaluation.
+#[derive(Debug, Clone)]
+pub(crate) struct Accessibility {
+    pub(crate) offsets: Vec<Offset>,
+    pub(crate) threshold: usize,
+    pub(crate) wrap: bool,
+}
+
+impl From<&Rule> for Accessibility {
+    fn from(rule: &Rule) -> Self {
+        let offsets = if rule.offset.is_empty() {
+            rule.neighbourhood.offsets()
+        } else {
+            rule.offset.clone()
+        };
+        Self {
+            offsets,
+            threshold: rule.threshold,
+            wrap: rule.wrap,
+        }
+    }
+}
+
+impl Accessibility {
+    fn displace<T>(&self, map: &Map<T>, point: Point, dx: i32, dy: i32) -> Option<Point> {
+        let mut point = Point::new(point.x + dx, point.y + dy);
+        if self.wrap {
+            point.x = point.x.rem_euclid(map.width() as i32);
+            point.y = point.y.rem_euclid(map.height() as i32);
+        }
+        map.in_bounds(point).then_some(point)
+    }
+
+    /// The in-bounds cells surrounding `point`.
+    pub(crate) fn neighbours<'a, T>(
+        &'a self,
+        map: &'a Map<T>,
+        point: Point,
+    ) -> impl 'a + Iterator<Item = Point> {
+        self.offsets
+            .iter()
+            .filter_map(move |offset| self.displace(map, point, offset.dx, offset.dy))
+    }
+
+    /// The in-bounds cells which `point` surrounds.
+    ///
+    /// For symmetric neighbourhoods these are the same as [`Self::neighbours`], but custom offsets need not be.
+    pub(crate) fn surrounded<'a, T>(
+        &'a self,
+        map: &'a Map<T>,
+        point: Point,
+    ) -> impl 'a + Iterator<Item = Point> {
+        self.offsets
+            .iter()
+            .filter_map(move |offset| self.displace(map, point, -offset.dx, -offset.dy))
+    }
+
+    pub(crate) fn adjacent_paper_rolls(&self, map: &Map<Tile>, point: Point) -> usize {
+        self.neighbours(map, point)
+            .filter(|adj| map[*adj] == Tile::PaperRoll)
+            .count()
+    }
+
+    pub(crate) fn is_accessable_by_forklift(&self, map: &Map<Tile>, point: Point) -> bool {
+        map.in_bounds(point)
+            && map[point] == Tile::PaperRoll
+            && self.adjacent_paper_rolls(map, point) < self.threshold
+    }
+}
+
+impl Default for Accessibility {
+    fn default() -> Self {
+        Self::from(&Rule::default())
+    }
+}
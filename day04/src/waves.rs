@@ -0,0 +1,227 @@
+//! Record the round in which each paper roll is removed.
+//!
+//! Rounds proceed as in part 2: every roll which is accessible at the start of a round is removed during it.
+//! Like [`crate::worklist`], we keep neighbour counts up to date, but process the worklist one round at a time:
+//! a roll whose count drops below the crowding threshold during some round becomes accessible in the next.
+
+use crate::{
+    rule::{Accessibility, Rule},
+    Tile,
+};
+use aoclib::geometry::{Direction, Map, Point};
+use color_eyre::{
+    eyre::{eyre, Context},
+    Result,
+};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+/// When a paper roll is removed, if at all.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Removal {
+    /// There was never a roll here.
+    #[default]
+    Empty,
+    /// The roll is never accessible.
+    Never,
+    /// The roll is removed in this round, counting from 1.
+    Round(u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum WaveFormat {
+    /// aligned columns of round numbers; `.` is empty and `@` is never removed
+    Text,
+    /// comma-separated round numbers; empty cells are blank and `never` is never removed
+    Csv,
+    /// plain grayscale image: empty is black, never removed is white, and later rounds are brighter
+    Pgm,
+}
+
+/// Compute the round in which each paper roll is removed.
+pub(crate) fn removal_waves(mut map: Map<Tile>, accessibility: &Accessibility) -> Map<Removal> {
+    let mut waves = Map::<Removal>::new(map.width(), map.height());
+    let mut neighbours = Map::<usize>::new(map.width(), map.height());
+    let mut accessible = Vec::new();
+    for (point, &tile) in map.iter() {
+        if tile == Tile::PaperRoll {
+            waves[point] = Removal::Never;
+            neighbours[point] = accessibility.adjacent_paper_rolls(&map, point);
+            if neighbours[point] < accessibility.threshold {
+                accessible.push(point);
+            }
+        }
+    }
+
+    let mut round = 0;
+    while !accessible.is_empty() {
+        round += 1;
+        // counts must reflect the state at the start of the round, so rolls are only removed once all are known
+        for &point in &accessible {
+            waves[point] = Removal::Round(round);
+            map[point] = Tile::Empty;
+        }
+        let mut next = Vec::new();
+        for point in accessible {
+            for adj in accessibility.surrounded(&map, point) {
+                if map[adj] == Tile::PaperRoll {
+                    neighbours[adj] -= 1;
+                    if neighbours[adj] + 1 == accessibility.threshold {
+                        next.push(adj);
+                    }
+                }
+            }
+        }
+        accessible = next;
+    }
+    waves
+}
+
+/// The final round in which any paper roll is removed, or 0 if none are.
+pub(crate) fn last_round(waves: &Map<Removal>) -> u32 {
+    waves
+        .iter()
+        .filter_map(|(_point, removal)| match removal {
+            Removal::Round(round) => Some(*round),
+            _ => None,
+        })
+        .max()
+        .unwrap_or_default()
+}
+
+/// The points of the map, row by row from the top.
+pub(crate) fn rows<T>(map: &Map<T>) -> impl '_ + Iterator<Item = impl Iterator<Item = Point>> {
+    let (dx, dy) = Direction::Down.deltas();
+    map.project(map.top_left(), dx, dy).map(move |left_edge| {
+        let (dx, dy) = Direction::Right.deltas();
+        map.project(left_edge, dx, dy)
+    })
+}
+
+fn write_waves(waves: &Map<Removal>, format: WaveFormat, mut out: impl Write) -> Result<()> {
+    let last_round = last_round(waves);
+
+    match format {
+        WaveFormat::Text => {
+            let width = last_round.to_string().len();
+            for row in rows(waves) {
+                let row = row
+                    .map(|point| match waves[point] {
+                        Removal::Empty => format!("{:>width$}", "."),
+                        Removal::Never => format!("{:>width$}", "@"),
+                        Removal::Round(round) => format!("{round:>width$}"),
+                    })
+                    .collect::<Vec<_>>();
+                writeln!(out, "{}", row.join(" "))?;
+            }
+        }
+        WaveFormat::Csv => {
+            for row in rows(waves) {
+                let row = row
+                    .map(|point| match waves[point] {
+                        Removal::Empty => String::new(),
+                        Removal::Never => "never".into(),
+                        Removal::Round(round) => round.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                writeln!(out, "{}", row.join(","))?;
+            }
+        }
+        WaveFormat::Pgm => {
+            let white = last_round + 1;
+            if white > u16::MAX.into() {
+                return Err(eyre!(
+                    "too many rounds ({last_round}) to represent in a PGM image"
+                ));
+            }
+            writeln!(out, "P2\n{} {}\n{white}", waves.width(), waves.height())?;
+            for row in rows(waves) {
+                let row = row
+                    .map(|point| match waves[point] {
+                        Removal::Empty => 0,
+                        Removal::Never => white,
+                        Removal::Round(round) => round,
+                    })
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>();
+                writeln!(out, "{}", row.join(" "))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Export the round in which each paper roll is removed, to `output` or else to stdout.
+pub fn waves(input: &Path, format: WaveFormat, output: Option<&Path>, rule: &Rule) -> Result<()> {
+    let map = <Map<Tile> as TryFrom<&Path>>::try_from(input)?;
+    let waves = removal_waves(map, &Accessibility::from(rule));
+    match output {
+        Some(path) => {
+            let file =
+                File::create(path).wrap_err_with(|| format!("creating {}", path.display()))?;
+            let mut out = BufWriter::new(file);
+            write_waves(&waves, format, &mut out)?;
+            out.flush()?;
+        }
+        None => write_waves(&waves, format, io::stdout().lock())?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remove_all_by_rescan;
+
+    #[test]
+    fn waves_match_rescan_rounds() {
+        for (seed, rule) in (1..).zip(crate::test_rules()) {
+            let accessibility = Accessibility::from(&rule);
+            let map = crate::random_map(seed, 31, 17, 200);
+
+            // replay the rescan one round at a time, checking which rolls go in each
+            let waves = removal_waves(map.clone(), &accessibility);
+            let mut current = map.clone();
+            let mut round = 0;
+            loop {
+                let accessible = current
+                    .iter()
+                    .filter(|(point, _tile)| {
+                        accessibility.is_accessable_by_forklift(&current, *point)
+                    })
+                    .map(|(point, _tile)| point)
+                    .collect::<Vec<_>>();
+                if accessible.is_empty() {
+                    break;
+                }
+                round += 1;
+                for point in accessible {
+                    assert_eq!(
+                        waves[point],
+                        Removal::Round(round),
+                        "seed {seed} rule {rule:?}"
+                    );
+                    current[point] = Tile::Empty;
+                }
+            }
+            for (point, tile) in current.iter() {
+                if *tile == Tile::PaperRoll {
+                    assert_eq!(waves[point], Removal::Never, "seed {seed} rule {rule:?}");
+                }
+            }
+
+            let removed = waves
+                .iter()
+                .filter(|(_point, removal)| matches!(removal, Removal::Round(_)))
+                .count();
+            assert_eq!(
+                removed as u32,
+                remove_all_by_rescan(map, &accessibility),
+                "seed {seed} rule {rule:?}"
+            );
+        }
+    }
+}
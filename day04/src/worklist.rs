@@ -0,0 +1,97 @@
+//! Remove paper rolls with a worklist instead of rescanning the whole map each round.
+//!
+//! Removing a roll only ever reduces the number of rolls adjacent to its neighbours, so an accessible roll
+//! stays accessible until it is removed, and the set of rolls which are eventually removed does not depend
+//! on the order of removal. We can therefore keep the neighbour count of each roll up to date as rolls are
+//! removed, and enqueue a roll exactly when its count drops below the crowding threshold.
+//!
+//! Each roll is enqueued at most once and each removal touches only its neighbours, so this is linear
+//! in the size of the map.
+
+use crate::{rule::Accessibility, Tile};
+use aoclib::geometry::Map;
+
+/// Remove accessible paper rolls until none remain, returning the total removed.
+pub(crate) fn remove_all(mut map: Map<Tile>, accessibility: &Accessibility) -> u32 {
+    let mut neighbours = Map::<usize>::new(map.width(), map.height());
+    let mut worklist = Vec::new();
+    for (point, &tile) in map.iter() {
+        if tile == Tile::PaperRoll {
+            neighbours[point] = accessibility.adjacent_paper_rolls(&map, point);
+            if neighbours[point] < accessibility.threshold {
+                worklist.push(point);
+            }
+        }
+    }
+
+    let mut total_removed = 0;
+    while let Some(point) = worklist.pop() {
+        map[point] = Tile::Empty;
+        total_removed += 1;
+        for adj in accessibility.surrounded(&map, point) {
+            if map[adj] == Tile::PaperRoll {
+                neighbours[adj] -= 1;
+                // rolls which were already accessible are already in the worklist
+                if neighbours[adj] + 1 == accessibility.threshold {
+                    worklist.push(adj);
+                }
+            }
+        }
+    }
+    total_removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{random_map, remove_all_by_rescan, test_rules};
+    use aoclib::geometry::Point;
+
+    fn map_from_rows(rows: &[&str]) -> Map<Tile> {
+        let mut map = Map::new(rows[0].len(), rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, char) in row.chars().enumerate() {
+                if char == '@' {
+                    map[Point::new(x as i32, y as i32)] = Tile::PaperRoll;
+                }
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn example() {
+        let map = map_from_rows(&[
+            "..@@.@@@@.",
+            "@@@.@.@.@@",
+            "@@@@@.@.@@",
+            "@.@@@@..@.",
+            "@@.@@@@.@@",
+            ".@@@@@@@.@",
+            ".@.@.@.@@@",
+            "@.@@@.@@@@",
+            ".@@@@@@@@.",
+            "@.@.@@@.@.",
+        ]);
+        let accessibility = Accessibility::default();
+        assert_eq!(remove_all(map.clone(), &accessibility), 43);
+        assert_eq!(remove_all_by_rescan(map, &accessibility), 43);
+    }
+
+    #[test]
+    fn matches_rescan() {
+        for rule in test_rules() {
+            let accessibility = Accessibility::from(&rule);
+            for seed in 1..=20 {
+                for density in [64, 128, 192, 224, 255] {
+                    let map = random_map(seed, 37, 23, density);
+                    assert_eq!(
+                        remove_all(map.clone(), &accessibility),
+                        remove_all_by_rescan(map, &accessibility),
+                        "seed {seed} density {density} rule {rule:?}"
+                    );
+                }
+            }
+        }
+    }
+}
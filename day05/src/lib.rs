@@ -2,73 +2,201 @@ use color_eyre::{
     Result,
     eyre::{Context, OptionExt, eyre},
 };
+use range_set::{Endpoint, RangeSet};
 use std::{
-    io::{BufRead, BufReader},
+    fmt,
+    io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write},
+    num::ParseIntError,
+    ops::RangeInclusive,
     path::Path,
     str::FromStr,
 };
 
-type IngredientId = u64;
+/// An integer type identifying ingredients.
+///
+/// Ids are 64-bit by default; the input format can select 128-bit ids instead.
+pub trait IngredientId: Endpoint + FromStr<Err = ParseIntError> + fmt::Display {}
+
+impl IngredientId for u64 {}
+impl IngredientId for u128 {}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Range {
-    low: IngredientId,
-    high: IngredientId,
+struct Range<Id> {
+    low: Id,
+    high: Id,
 }
 
-impl Range {
-    fn contains(&self, ingredient: IngredientId) -> bool {
-        self.low <= ingredient && self.high >= ingredient
+impl<Id> From<Range<Id>> for RangeInclusive<Id> {
+    fn from(range: Range<Id>) -> Self {
+        range.low..=range.high
+    }
+}
+
+impl<Id: IngredientId> fmt::Display for Range<Id> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.low, self.high)
     }
+}
 
-    // yeah, of course these ingredient IDs are already u64s,
-    // but semantically a count qty is very different from an
-    // ingredient id, and the casts serve to highlight that
-    #[allow(clippy::unnecessary_cast)]
-    fn count(&self) -> u64 {
-        self.high as u64 - self.low as u64 + 1
+impl<Id: IngredientId> Range<Id> {
+    fn contains(&self, ingredient: Id) -> bool {
+        self.low <= ingredient && self.high >= ingredient
     }
 }
 
-impl FromStr for Range {
+impl<Id: IngredientId> FromStr for Range<Id> {
     type Err = color_eyre::eyre::Report;
 
     fn from_str(s: &str) -> Result<Self> {
         let dash = s.find('-').ok_or_eyre("no hyphen")?;
         let (low, rest) = s.split_at(dash);
         let (_dash, high) = rest.split_at(1);
-        let low = low.parse()?;
-        let high = high.parse()?;
+        let low = low.parse::<Id>().wrap_err("parsing low bound")?;
+        let high = high.parse::<Id>().wrap_err("parsing high bound")?;
+        if low > high {
+            return Err(eyre!("low bound {low} exceeds high bound {high}"));
+        }
         Ok(Self { low, high })
     }
 }
 
+/// An available ingredient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Available<Id> {
+    id: Id,
+    /// The number of fresh ranges declared before this ingredient.
+    known_ranges: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    FreshRanges,
+    AvailableIngredients,
+}
+
+impl Section {
+    fn name(self) -> &'static str {
+        match self {
+            Self::FreshRanges => "fresh ranges",
+            Self::AvailableIngredients => "available ingredients",
+        }
+    }
+}
+
+/// How the input is laid out.
+#[derive(Debug, Default, Clone, Copy, clap::Args)]
+pub struct InputFormat {
+    /// accept several blocks of fresh ranges and available ingredients, alternating and separated by blank lines
+    ///
+    /// each ingredient is fresh if it is covered by any range declared before it
+    #[arg(long)]
+    pub alternating: bool,
+
+    /// parse ingredient ids as 128-bit integers instead of 64-bit
+    #[arg(long)]
+    pub wide_ids: bool,
+}
+
+/// Whether the input must include a section of available ingredients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Ingredients {
+    Required,
+    Optional,
+}
+
 #[derive(Debug)]
-pub struct Input {
-    fresh_ranges: Vec<Range>,
-    available: Vec<IngredientId>,
+pub struct Input<Id> {
+    fresh_ranges: Vec<Range<Id>>,
+    available: Vec<Available<Id>>,
 }
 
-impl Input {
-    fn from_path(input: &Path) -> Result<Self> {
+impl<Id: IngredientId> Input<Id> {
+    fn from_path(input: &Path, format: InputFormat, ingredients: Ingredients) -> Result<Self> {
+        let file = std::fs::File::open(input).wrap_err("opening input file")?;
+        Self::from_reader(BufReader::new(file), format, ingredients)
+    }
+
+    /// Parse fresh ranges, then a blank line, then available ingredients.
+    ///
+    /// When the format is alternating, a further blank line returns to fresh ranges, and so on.
+    fn from_reader(
+        reader: impl BufRead,
+        format: InputFormat,
+        ingredients: Ingredients,
+    ) -> Result<Self> {
         let mut fresh_ranges = Vec::new();
         let mut available = Vec::new();
+        let mut section = Section::FreshRanges;
+        // the line number of a blank line which ended the available ingredients, when not alternating
+        let mut ended_at = None;
+        // blank lines before any content separate nothing, so they are skipped like repeated blank lines
+        let mut previous_blank = true;
 
-        let reader = BufReader::new(std::fs::File::open(input).wrap_err("opening input file")?);
         for (line_number_0, line) in reader.lines().enumerate() {
             let line_number = line_number_0 + 1;
             let line = line.wrap_err_with(|| format!("reading line {line_number}"))?;
+            let line = line.trim();
 
             if line.is_empty() {
+                // consecutive blank lines form a single separator
+                if std::mem::replace(&mut previous_blank, true) {
+                    continue;
+                }
+                match section {
+                    Section::FreshRanges => section = Section::AvailableIngredients,
+                    Section::AvailableIngredients if format.alternating => {
+                        section = Section::FreshRanges
+                    }
+                    Section::AvailableIngredients => {
+                        ended_at.get_or_insert(line_number);
+                    }
+                }
                 continue;
             }
-            if let Ok(range) = line.parse::<Range>() {
-                fresh_ranges.push(range);
-            } else if let Ok(ingredient) = line.parse::<IngredientId>() {
-                available.push(ingredient);
-            } else {
-                return Err(eyre!("failed to parse line {line_number}: {line}"));
+            previous_blank = false;
+            if let Some(blank_line) = ended_at {
+                return Err(eyre!(
+                    "line {line_number}: unexpected content after blank line {blank_line} ended the {} section; \
+                    use alternating blocks to declare more fresh ranges",
+                    section.name()
+                ));
             }
+
+            let parsed = match section {
+                Section::FreshRanges => line.parse::<Range<Id>>().map(|range| {
+                    fresh_ranges.push(range);
+                }),
+                Section::AvailableIngredients => line.parse::<Id>().map_err(Into::into).map(|id| {
+                    available.push(Available {
+                        id,
+                        known_ranges: fresh_ranges.len(),
+                    });
+                }),
+            };
+            parsed.map_err(|err| {
+                // point out the likely cause when a line belongs in the other section
+                let err = match section {
+                    Section::FreshRanges if line.parse::<Id>().is_ok() => err.wrap_err(
+                        "found an ingredient id; is the blank line before the available ingredients missing?",
+                    ),
+                    Section::AvailableIngredients if line.parse::<Range<Id>>().is_ok() => err
+                        .wrap_err("found a range; fresh ranges must precede the available ingredients"),
+                    _ => err,
+                };
+                err.wrap_err(format!(
+                    "line {line_number}: in {} section: parsing {line:?}",
+                    section.name()
+                ))
+            })?;
+        }
+
+        if section == Section::FreshRanges
+            && !format.alternating
+            && ingredients == Ingredients::Required
+        {
+            return Err(eyre!(
+                "input ended in the fresh ranges section; expected a blank line followed by available ingredients"
+            ));
         }
 
         Ok(Self {
@@ -77,62 +205,469 @@ impl Input {
         })
     }
 
-    fn consolidate_ranges(&mut self) {
-        self.fresh_ranges.sort_unstable_by_key(|range| range.low);
-        let mut consolidated = Vec::new();
-
-        // index of range containing low bound
-        let mut low_idx = 0;
-        while low_idx < self.fresh_ranges.len() {
-            let low = self.fresh_ranges[low_idx].low;
-            let mut high = self.fresh_ranges[low_idx].high;
-            let mut high_idx = low_idx + 1;
+    /// All fresh ingredient ids, consolidated.
+    fn fresh(&self) -> RangeSet<Id> {
+        self.fresh_ranges.iter().copied().map(Into::into).collect()
+    }
 
-            while let Some(high_range) = self.fresh_ranges.get(high_idx).copied()
-                && high_range.low <= high + 1
-            {
-                high_idx += 1;
-                high = high.max(high_range.high);
+    /// Visit each available ingredient along with the ids known to be fresh when it was declared.
+    fn for_each_available(&self, mut visit: impl FnMut(Available<Id>, &RangeSet<Id>)) {
+        let mut fresh = RangeSet::new();
+        let mut known_ranges = 0;
+        for &ingredient in &self.available {
+            if ingredient.known_ranges > known_ranges {
+                fresh.extend(
+                    self.fresh_ranges[known_ranges..ingredient.known_ranges]
+                        .iter()
+                        .copied()
+                        .map(Into::into),
+                );
+                known_ranges = ingredient.known_ranges;
             }
+            visit(ingredient, &fresh);
+        }
+    }
+}
 
-            consolidated.push(Range { low, high });
-            low_idx = high_idx;
+/// Call a function generic over the ingredient id type with the type selected by the input format.
+macro_rules! with_id_type {
+    ($format:expr, $f:ident($($arg:expr),*)) => {
+        if $format.wide_ids {
+            $f::<u128>($($arg),*)
+        } else {
+            $f::<u64>($($arg),*)
         }
+    };
+}
 
-        self.fresh_ranges = consolidated;
-    }
+fn solve_part1<Id: IngredientId>(input: &Path, format: InputFormat) -> Result<()> {
+    let input = Input::<Id>::from_path(input, format, Ingredients::Required)?;
+    let mut n_fresh = 0;
+    input.for_each_available(|ingredient, fresh| {
+        if fresh.contains(ingredient.id) {
+            n_fresh += 1;
+        }
+    });
+    println!("n fresh ingredients: {n_fresh}");
+    Ok(())
+}
 
-    fn is_fresh(&self, ingredient: IngredientId) -> bool {
-        debug_assert!(
-            self.fresh_ranges
-                .windows(2)
-                .all(|window| window[0].high < window[1].low),
-            "all ranges must be consolidated before calling this method"
-        );
-        // could maybe get fancy with a binary search, but let's try this for now
-        self.fresh_ranges
+pub fn part1(input: &Path, format: InputFormat) -> Result<()> {
+    with_id_type!(format, solve_part1(input, format))
+}
+
+fn solve_part2<Id: IngredientId>(input: &Path, format: InputFormat) -> Result<()> {
+    let input = Input::<Id>::from_path(input, format, Ingredients::Required)?;
+    let total_fresh = input
+        .fresh()
+        .count()
+        .ok_or_eyre("every 128-bit ingredient id is fresh; the total does not fit in a u128")?;
+    println!("total fresh ingredients (pt 2): {total_fresh}");
+    Ok(())
+}
+
+pub fn part2(input: &Path, format: InputFormat) -> Result<()> {
+    with_id_type!(format, solve_part2(input, format))
+}
+
+/// The freshness of each available ingredient, and the ranges which determined it.
+#[derive(Debug)]
+struct Report<Id> {
+    /// Each available ingredient, with the indices of the original ranges covering it.
+    ingredients: Vec<(Id, Vec<usize>)>,
+    /// Indices of the original ranges which cover no available ingredient.
+    unused: Vec<usize>,
+    /// The widest span of ids between consolidated fresh ranges.
+    largest_gap: Option<RangeInclusive<Id>>,
+}
+
+/// The number of ids in a gap between ranges, which never includes every id.
+fn gap_len<Id: IngredientId>(gap: &RangeInclusive<Id>) -> u128 {
+    Id::span(*gap.start(), *gap.end()).expect("a gap excludes at least two ids")
+}
+
+impl<Id: IngredientId> Report<Id> {
+    fn new(input: &Input<Id>) -> Self {
+        let mut used = vec![false; input.fresh_ranges.len()];
+        let ingredients = input
+            .available
             .iter()
-            .any(|range| range.contains(ingredient))
+            .map(|ingredient| {
+                let covering = input.fresh_ranges[..ingredient.known_ranges]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_idx, range)| range.contains(ingredient.id))
+                    .map(|(idx, _range)| idx)
+                    .collect::<Vec<_>>();
+                for &idx in &covering {
+                    used[idx] = true;
+                }
+                (ingredient.id, covering)
+            })
+            .collect();
+        let unused = used
+            .iter()
+            .enumerate()
+            .filter(|(_idx, used)| !**used)
+            .map(|(idx, _used)| idx)
+            .collect();
+        // `max_by_key` returns the last maximum, so reverse to prefer the earliest gap
+        let largest_gap = input.fresh().gaps().rev().max_by_key(gap_len);
+
+        Self {
+            ingredients,
+            unused,
+            largest_gap,
+        }
     }
 }
 
-pub fn part1(input: &Path) -> Result<()> {
-    let mut input = Input::from_path(input)?;
-    input.consolidate_ranges();
-    let n_fresh = input
-        .available
+fn write_report<Id: IngredientId>(input: &Path, format: InputFormat) -> Result<()> {
+    let input = Input::<Id>::from_path(input, format, Ingredients::Required)?;
+    let report = Report::new(&input);
+    let describe = |idx: usize| format!("#{} ({})", idx + 1, input.fresh_ranges[idx]);
+
+    let mut spoiled = Vec::new();
+    for (id, covering) in &report.ingredients {
+        if covering.is_empty() {
+            spoiled.push(id.to_string());
+            println!("ingredient {id}: spoiled");
+        } else {
+            let covering = covering.iter().copied().map(describe).collect::<Vec<_>>();
+            println!("ingredient {id}: fresh, covered by {}", covering.join(", "));
+        }
+    }
+
+    println!();
+    println!(
+        "fresh ingredients: {} of {}",
+        report.ingredients.len() - spoiled.len(),
+        report.ingredients.len()
+    );
+    let list = |items: &[String]| {
+        if items.is_empty() {
+            "none".into()
+        } else {
+            items.join(", ")
+        }
+    };
+    println!(
+        "spoiled ingredients ({}): {}",
+        spoiled.len(),
+        list(&spoiled)
+    );
+    let unused = report
+        .unused
         .iter()
         .copied()
-        .filter(|&ingredient| input.is_fresh(ingredient))
-        .count();
-    println!("n fresh ingredients: {n_fresh}");
+        .map(describe)
+        .collect::<Vec<_>>();
+    println!(
+        "ranges covering no available ingredient ({}): {}",
+        unused.len(),
+        list(&unused)
+    );
+    match report.largest_gap {
+        Some(gap) => println!(
+            "largest gap between fresh ranges: {}-{} ({} ids)",
+            gap.start(),
+            gap.end(),
+            gap_len(&gap)
+        ),
+        None => println!("largest gap between fresh ranges: none"),
+    }
     Ok(())
 }
 
-pub fn part2(input: &Path) -> Result<()> {
-    let mut input = Input::from_path(input)?;
-    input.consolidate_ranges();
-    let total_fresh = input.fresh_ranges.iter().map(Range::count).sum::<u64>();
-    println!("total fresh ingredients (pt 2): {total_fresh}");
+/// List each available ingredient with its freshness and the original ranges covering it, then summarize.
+///
+/// Ranges are numbered from 1 in the order they appear in the input.
+pub fn report(input: &Path, format: InputFormat) -> Result<()> {
+    with_id_type!(format, write_report(input, format))
+}
+
+/// Tallies of the queries answered by [`serve`].
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Tally {
+    fresh: u64,
+    spoiled: u64,
+    invalid: u64,
+}
+
+/// Answer one query per line of `queries`, writing one answer per query to `answers`.
+///
+/// Blank lines are skipped. Lines which are not ingredient ids are answered as invalid rather than ending
+/// the stream, so that answers remain aligned with queries.
+fn answer_queries<Id: IngredientId>(
+    fresh: &RangeSet<Id>,
+    queries: impl BufRead,
+    mut answers: impl Write,
+    flush_each_line: bool,
+) -> Result<Tally> {
+    let mut tally = Tally::default();
+    for (line_number_0, line) in queries.lines().enumerate() {
+        let line_number = line_number_0 + 1;
+        let line = line.wrap_err_with(|| format!("reading query {line_number}"))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        match line.parse::<Id>() {
+            Ok(id) => match fresh.range_containing(id) {
+                Some(range) => {
+                    tally.fresh += 1;
+                    writeln!(answers, "{id} fresh {}-{}", range.start(), range.end())?;
+                }
+                None => {
+                    tally.spoiled += 1;
+                    writeln!(answers, "{id} spoiled")?;
+                }
+            },
+            Err(err) => {
+                tally.invalid += 1;
+                writeln!(answers, "{line} invalid: {err}")?;
+            }
+        }
+        if flush_each_line {
+            answers.flush()?;
+        }
+    }
+    answers.flush()?;
+    Ok(tally)
+}
+
+fn run_server<Id: IngredientId>(input: &Path, format: InputFormat) -> Result<()> {
+    let input = Input::<Id>::from_path(input, format, Ingredients::Optional)?;
+    let fresh = input.fresh();
+
+    let stdin = io::stdin();
+    // answer interactive queries immediately, but buffer when piped
+    let flush_each_line = stdin.is_terminal();
+    let answers = BufWriter::new(io::stdout().lock());
+    let tally = answer_queries(&fresh, stdin.lock(), answers, flush_each_line)?;
+    eprintln!(
+        "answered {} queries: {} fresh, {} spoiled, {} invalid",
+        tally.fresh + tally.spoiled + tally.invalid,
+        tally.fresh,
+        tally.spoiled,
+        tally.invalid
+    );
     Ok(())
 }
+
+/// Load the fresh ranges, then answer freshness queries streamed on stdin, one ingredient id per line.
+///
+/// Each answer is written to stdout as `ID fresh LOW-HIGH`, naming the consolidated range containing the
+/// ingredient, or `ID spoiled`. Any available ingredients in the input are ignored.
+pub fn serve(input: &Path, format: InputFormat) -> Result<()> {
+    with_id_type!(format, run_server(input, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "3-5\n10-14\n16-20\n12-18\n\n1\n5\n8\n11\n17\n32\n";
+
+    fn parse(input: &str, alternating: bool) -> Result<Input<u64>> {
+        Input::from_reader(
+            input.as_bytes(),
+            InputFormat {
+                alternating,
+                ..InputFormat::default()
+            },
+            Ingredients::Required,
+        )
+    }
+
+    fn n_fresh(input: &Input<u64>) -> usize {
+        let mut n_fresh = 0;
+        input.for_each_available(|ingredient, fresh| {
+            n_fresh += usize::from(fresh.contains(ingredient.id));
+        });
+        n_fresh
+    }
+
+    /// The full chain of an error, for matching against expected messages.
+    fn error_chain(input: &str, alternating: bool) -> String {
+        format!("{:#}", parse(input, alternating).unwrap_err())
+    }
+
+    #[test]
+    fn example() {
+        let input = parse(EXAMPLE, false).unwrap();
+        assert_eq!(input.fresh_ranges.len(), 4);
+        assert_eq!(input.available.len(), 6);
+        assert_eq!(n_fresh(&input), 3);
+        assert_eq!(input.fresh().count(), Some(14));
+
+        // extra blank lines are harmless, including before the first range
+        assert!(parse(&format!("{EXAMPLE}\n\n"), false).is_ok());
+        assert_eq!(
+            n_fresh(&parse(&format!("\n\n{EXAMPLE}"), false).unwrap()),
+            3
+        );
+        assert_eq!(n_fresh(&parse("\n3-5\n\n1\n", false).unwrap()), 0);
+        let spaced = EXAMPLE.replace("\n\n", "\n\n\n");
+        assert_eq!(n_fresh(&parse(&spaced, false).unwrap()), 3);
+    }
+
+    #[test]
+    fn section_errors() {
+        let chain = error_chain("3-5\n10-\n\n1\n", false);
+        assert!(chain.contains("line 2: in fresh ranges section"), "{chain}");
+        assert!(chain.contains("parsing high bound"), "{chain}");
+
+        let chain = error_chain("3-5\n7\n\n1\n", false);
+        assert!(chain.contains("line 2: in fresh ranges section"), "{chain}");
+        assert!(
+            chain.contains("blank line before the available ingredients missing"),
+            "{chain}"
+        );
+
+        let chain = error_chain("3-5\n\n1\n7-8\n", false);
+        assert!(
+            chain.contains("line 4: in available ingredients section"),
+            "{chain}"
+        );
+        assert!(chain.contains("fresh ranges must precede"), "{chain}");
+
+        let chain = error_chain("5-3\n\n1\n", false);
+        assert!(
+            chain.contains("low bound 5 exceeds high bound 3"),
+            "{chain}"
+        );
+
+        let chain = error_chain("3-5\n10-14\n", false);
+        assert!(
+            chain.contains("ended in the fresh ranges section"),
+            "{chain}"
+        );
+    }
+
+    #[test]
+    fn alternating_blocks() {
+        let input = "3-5\n\n4\n11\n\n10-14\n\n4\n11\n";
+        let chain = error_chain(input, false);
+        assert!(
+            chain.contains("line 6: unexpected content after blank line 5"),
+            "{chain}"
+        );
+
+        // 11 is spoiled when first queried, but fresh once `10-14` has been declared
+        let input = parse(input, true).unwrap();
+        assert_eq!(input.fresh_ranges.len(), 2);
+        assert_eq!(n_fresh(&input), 3);
+
+        // a final block of fresh ranges needs no queries
+        assert!(parse("3-5\n\n4\n\n10-14\n", true).is_ok());
+    }
+
+    #[test]
+    fn serve_answers_each_query() {
+        let input = Input::<u64>::from_reader(
+            "3-5\n10-14\n16-20\n12-18\n".as_bytes(),
+            InputFormat::default(),
+            Ingredients::Optional,
+        )
+        .unwrap();
+        let mut answers = Vec::new();
+        let tally = answer_queries(
+            &input.fresh(),
+            "1\n5\n\n17\nbanana\n18446744073709551615\n".as_bytes(),
+            &mut answers,
+            false,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(answers).unwrap(),
+            "1 spoiled\n\
+             5 fresh 3-5\n\
+             17 fresh 10-20\n\
+             banana invalid: invalid digit found in string\n\
+             18446744073709551615 spoiled\n"
+        );
+        assert_eq!(
+            tally,
+            Tally {
+                fresh: 2,
+                spoiled: 2,
+                invalid: 1
+            }
+        );
+    }
+
+    #[test]
+    fn report_lists_covering_ranges() {
+        let input = parse(EXAMPLE, false).unwrap();
+        let report = Report::new(&input);
+        assert_eq!(
+            report.ingredients,
+            [
+                (1, vec![]),
+                (5, vec![0]),
+                (8, vec![]),
+                (11, vec![1]),
+                (17, vec![2, 3]),
+                (32, vec![]),
+            ]
+        );
+        assert!(report.unused.is_empty());
+        assert_eq!(report.largest_gap, Some(6..=9));
+
+        let input = parse("1-2\n5-6\n9-10\n20-30\n34-35\n\n1\n", false).unwrap();
+        let report = Report::new(&input);
+        assert_eq!(report.unused, [1, 2, 3, 4]);
+        // the two widest gaps are equal, so the first is reported
+        assert_eq!(report.largest_gap, Some(11..=19));
+
+        // ranges declared after an ingredient do not cover it
+        let input = parse("3-5\n\n11\n\n10-14\n\n11\n", true).unwrap();
+        let report = Report::new(&input);
+        assert_eq!(report.ingredients, [(11, vec![]), (11, vec![1])]);
+    }
+
+    #[test]
+    fn full_domain_bounds() {
+        // the whole 64-bit domain holds one more id than fits in a u64
+        let input = parse("0-18446744073709551615\n\n18446744073709551615\n", false).unwrap();
+        assert_eq!(input.fresh().count(), Some(1 << 64));
+        assert_eq!(n_fresh(&input), 1);
+
+        // ranges meeting at the top of the domain merge without overflow
+        let input = parse(
+            "18446744073709551614-18446744073709551615\n0-18446744073709551613\n\n0\n",
+            false,
+        )
+        .unwrap();
+        assert_eq!(input.fresh().iter().count(), 1);
+        assert_eq!(input.fresh().count(), Some(1 << 64));
+
+        let chain = error_chain("0-18446744073709551616\n\n0\n", false);
+        assert!(chain.contains("parsing high bound"), "{chain}");
+
+        let wide = |input: &str| {
+            Input::<u128>::from_reader(
+                input.as_bytes(),
+                InputFormat {
+                    wide_ids: true,
+                    ..InputFormat::default()
+                },
+                Ingredients::Required,
+            )
+            .unwrap()
+        };
+        let input = wide("1-340282366920938463463374607431768211455\n\n0\n");
+        assert_eq!(input.fresh().count(), Some(u128::MAX));
+        let input = wide("0-340282366920938463463374607431768211455\n\n0\n");
+        assert_eq!(input.fresh().count(), None);
+
+        let input = wide(
+            "0-1\n340282366920938463463374607431768211454-340282366920938463463374607431768211455\n\n0\n",
+        );
+        let report = Report::new(&input);
+        assert_eq!(report.largest_gap, Some(2..=u128::MAX - 2));
+        assert_eq!(gap_len(&report.largest_gap.unwrap()), u128::MAX - 3);
+    }
+}
@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day05::{part1, part2};
+use day05::{InputFormat, part1, part2, report, serve};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -22,6 +22,17 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    #[command(flatten)]
+    format: InputFormat,
+
+    /// load the fresh ranges, then answer ingredient id queries from stdin instead of running the puzzle parts
+    #[arg(long)]
+    serve: bool,
+
+    /// list each available ingredient with the ranges covering it, then summarize, instead of running the puzzle parts
+    #[arg(long, conflicts_with = "serve")]
+    report: bool,
 }
 
 impl RunArgs {
@@ -44,11 +55,18 @@ fn main() -> Result<()> {
     let args = RunArgs::parse();
     let input_path = args.input()?;
 
+    if args.serve {
+        return serve(&input_path, args.format);
+    }
+    if args.report {
+        return report(&input_path, args.format);
+    }
+
     if !args.no_part1 {
-        part1(&input_path)?;
+        part1(&input_path, args.format)?;
     }
     if args.part2 {
-        part2(&input_path)?;
+        part2(&input_path, args.format)?;
     }
     Ok(())
 }
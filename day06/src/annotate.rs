@@ -0,0 +1,207 @@
+//! Print the worksheet with each problem's result and numbers beside its operator.
+//!
+//! The worksheet is reproduced as it appears in the input. Each problem is labelled with its result followed by
+//! its numbers as read, so it is easy to see how the columns were split into problems. Labels are written on the
+//! far side of the operator line: below it when the operators are the bottom row, to the right of it when they
+//! are the right column, and so on. Where labels would collide, they are staggered onto further lines. Problems
+//! which could not be solved are labelled `!`, and their errors are listed after the worksheet.
+
+use crate::{
+    worksheet::{Orientation, Placement, Reading, Worksheet},
+    Problem,
+};
+use color_eyre::{eyre::eyre, Result};
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+/// Which part's reading of the numbers to annotate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Part {
+    /// lines parallel to the operators each hold one number
+    #[value(name = "1")]
+    One,
+    /// lines perpendicular to the operators each hold one number
+    #[value(name = "2")]
+    Two,
+}
+
+/// Lay out labels along a line, each starting at its own position, staggering them onto further lines
+/// where they would touch an earlier label.
+fn stagger<'a>(labels: impl IntoIterator<Item = (usize, &'a str)>) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for (position, label) in labels {
+        // positions are in characters, and labels are ascii, so byte lengths are widths
+        let line = match lines
+            .iter_mut()
+            .find(|line| line.is_empty() || line.len() < position)
+        {
+            Some(line) => line,
+            None => {
+                lines.push(String::new());
+                lines.last_mut().expect("just pushed a line")
+            }
+        };
+        line.push_str(&" ".repeat(position - line.len()));
+        line.push_str(label);
+    }
+    lines
+}
+
+fn write_annotated(
+    worksheet: &Worksheet,
+    reading: Reading,
+    orientation: Orientation,
+    mut out: impl Write,
+) -> Result<usize> {
+    let solved = worksheet
+        .blocks
+        .iter()
+        .map(|block| {
+            let problem = block.read(reading, orientation)?;
+            let result = problem.solve()?;
+            Ok((problem, result))
+        })
+        .collect::<Vec<Result<(Problem, u128)>>>();
+    let labels = solved
+        .iter()
+        .map(|solved| match solved {
+            Ok((problem, result)) => format!("{result} = {}", problem.expression()),
+            Err(_) => "!".into(),
+        })
+        .collect::<Vec<_>>();
+    let positioned = || {
+        worksheet
+            .blocks
+            .iter()
+            .zip(&labels)
+            .map(|(block, label)| (block.operator, label.as_str()))
+    };
+
+    let rows = worksheet
+        .grid
+        .iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>();
+    match worksheet.placement {
+        Placement::Bottom => {
+            for row in rows.iter().chain(&stagger(positioned())) {
+                writeln!(out, "{}", row.trim_end())?;
+            }
+        }
+        Placement::Top => {
+            for row in stagger(positioned()).iter().rev().chain(&rows) {
+                writeln!(out, "{}", row.trim_end())?;
+            }
+        }
+        Placement::Left | Placement::Right => {
+            // each row holds at most one operator, so there is never anything to stagger
+            let mut row_labels = vec![""; rows.len()];
+            for (position, label) in positioned() {
+                row_labels[position] = label;
+            }
+            let width = labels.iter().map(String::len).max().unwrap_or_default();
+            for (row, label) in rows.iter().zip(row_labels) {
+                let annotated = if worksheet.placement == Placement::Left {
+                    format!("{label:>width$} {row}")
+                } else {
+                    format!("{row} {label}")
+                };
+                writeln!(out, "{}", annotated.trim_end())?;
+            }
+        }
+    }
+
+    writeln!(out)?;
+    let mut grand_total = Some(0_u128);
+    let mut failures = 0;
+    for solved in &solved {
+        match solved {
+            Ok((_, result)) => {
+                grand_total = grand_total.and_then(|total| total.checked_add(*result));
+            }
+            Err(err) => {
+                writeln!(out, "{err:#}")?;
+                failures += 1;
+            }
+        }
+    }
+    match grand_total {
+        Some(grand_total) if failures == 0 => writeln!(out, "grand total: {grand_total}")?,
+        Some(_) => writeln!(
+            out,
+            "grand total: unknown; {failures} of {} problems failed",
+            solved.len()
+        )?,
+        None => writeln!(out, "grand total: overflows u128")?,
+    }
+    Ok(failures)
+}
+
+/// Print the worksheet with each problem's result and numbers beside its operator, reading numbers as in `part`.
+pub fn annotate(input: &Path, part: Part, orientation: Orientation) -> Result<()> {
+    let worksheet = Worksheet::from_path(input, orientation.operators)?;
+    let reading = match part {
+        Part::One => Reading::along(orientation.operators),
+        Part::Two => Reading::across(orientation.operators),
+    };
+    let failures = write_annotated(&worksheet, reading, orientation, io::stdout().lock())?;
+    if failures > 0 {
+        return Err(eyre!(
+            "{failures} of {} problems could not be solved",
+            worksheet.blocks.len()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn annotated(input: &str, reading: Reading, orientation: Orientation) -> String {
+        let worksheet = Worksheet::from_reader(input.as_bytes(), orientation.operators).unwrap();
+        let mut out = Vec::new();
+        write_annotated(&worksheet, reading, orientation, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn example() {
+        let input = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*   +   *   +  \n";
+        assert_eq!(
+            annotated(input, Reading::Columns, Orientation::default()),
+            "123 328  51 64\n 45 64  387 23\n  6 98  215 314\n*   +   *   +\n\
+             8544 = 1 * 24 * 356\n\
+             \x20   625 = 369 + 248 + 8\n\
+             \x20       3253600 = 32 * 581 * 175\n\
+             \x20           1058 = 623 + 431 + 4\n\n\
+             grand total: 3263827\n"
+        );
+
+        let right = Orientation {
+            operators: Placement::Right,
+            ..Orientation::default()
+        };
+        assert_eq!(
+            annotated("12 *\n 4\n\n56 +\n 7\n", Reading::Rows, right),
+            "12 * 48 = 12 * 4\n 4\n\n56 + 63 = 56 + 7\n 7\n\n\
+             grand total: 111\n"
+        );
+
+        // unsolvable problems are marked on the worksheet, and their errors follow it
+        let worksheet =
+            Worksheet::from_reader("12 3\n 5 0\n-  /\n".as_bytes(), Placement::Bottom).unwrap();
+        let mut out = Vec::new();
+        let failures =
+            write_annotated(&worksheet, Reading::Rows, Orientation::default(), &mut out).unwrap();
+        assert_eq!(failures, 1);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "12 3\n 5 0\n-  /\n7 = 12 - 5\n   !\n\n\
+             solving problem at column 4: 3 / 0: 3 / 0 divides by zero\n\
+             grand total: unknown; 1 of 2 problems failed\n"
+        );
+    }
+}
@@ -1,187 +1,175 @@
+mod annotate;
+mod worksheet;
+
 use color_eyre::{
     eyre::{eyre, Context, OptionExt},
     Result,
 };
-use itertools::{Itertools, Position};
-use std::{
-    io::{BufRead, BufReader},
-    path::Path,
-};
+use std::path::Path;
+use worksheet::{Location, Reading, Worksheet};
 
+pub use annotate::{annotate, Part};
+pub use worksheet::{Orientation, Placement};
+
+/// An operator combining the numbers of a problem.
+///
+/// Numbers are combined in the order they are read, so `-` with the numbers `12`, `5`, `3` computes
+/// `(12 - 5) - 3`. Intermediate results may not be negative, and division truncates.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, parse_display::FromStr, parse_display::Display)]
 enum Operation {
     #[display("+")]
     Sum,
     #[display("*")]
     Product,
+    #[display("-")]
+    Difference,
+    #[display("/")]
+    Quotient,
+    /// the least number
+    #[display("<")]
+    Min,
+    /// the greatest number
+    #[display(">")]
+    Max,
+    /// append the decimal digits of each number to the result so far
+    #[display("|")]
+    Concatenation,
 }
 
-struct Input {
-    rows: Vec<Vec<u64>>,
-    operations: Vec<Operation>,
-}
-
-impl Input {
-    fn parse(input: &Path) -> Result<Self> {
-        let file = std::fs::File::open(input)?;
-        let reader = BufReader::new(file);
-
-        let mut rows = Vec::new();
-        let mut operations = Vec::new();
-
-        for (position, row) in reader.lines().with_position() {
-            let row = row.wrap_err_with(|| format!("{position:?} row could not be read"))?;
-            match position {
-                Position::Only => return Err(eyre!("not enough rows in input")),
-                Position::First | Position::Middle => {
-                    let row = row
-                        .split_whitespace()
-                        .map(|value| value.parse::<u64>().map_err(Into::into))
-                        .collect::<Result<Vec<_>>>()?;
-                    rows.push(row);
-                }
-                Position::Last => {
-                    operations = row
-                        .split_whitespace()
-                        .map(|value| value.parse::<Operation>().map_err(Into::into))
-                        .collect::<Result<_>>()?;
-                }
+impl Operation {
+    fn apply(self, lhs: u128, rhs: u128) -> Result<u128> {
+        match self {
+            Self::Sum => lhs.checked_add(rhs).ok_or_eyre("sum overflows u128"),
+            Self::Product => lhs.checked_mul(rhs).ok_or_eyre("product overflows u128"),
+            Self::Difference => lhs
+                .checked_sub(rhs)
+                .ok_or_else(|| eyre!("{lhs} - {rhs} is negative")),
+            Self::Quotient => lhs
+                .checked_div(rhs)
+                .ok_or_else(|| eyre!("{lhs} / {rhs} divides by zero")),
+            Self::Min => Ok(lhs.min(rhs)),
+            Self::Max => Ok(lhs.max(rhs)),
+            Self::Concatenation => {
+                let digits = rhs.checked_ilog10().unwrap_or_default() + 1;
+                10_u128
+                    .checked_pow(digits)
+                    .and_then(|shift| lhs.checked_mul(shift))
+                    .and_then(|shifted| shifted.checked_add(rhs))
+                    .ok_or_eyre("concatenation overflows u128")
             }
         }
-
-        if rows.iter().any(|row| row.len() != operations.len()) {
-            return Err(eyre!("not all rows and operations had the same width"));
-        }
-
-        Ok(Self { rows, operations })
-    }
-
-    fn problems(&self) -> impl '_ + Iterator<Item = Problem> {
-        self.operations
-            .iter()
-            .copied()
-            .enumerate()
-            .map(|(column, operation)| {
-                let values = (0..self.rows.len())
-                    .map(|row| self.rows[row][column])
-                    .collect();
-                Problem { operation, values }
-            })
     }
 }
 
+#[derive(Debug)]
 struct Problem {
     operation: Operation,
+    location: Location,
     values: Vec<u64>,
 }
 
 impl Problem {
-    fn solve(&self) -> u64 {
-        match self.operation {
-            Operation::Sum => self.values.iter().sum(),
-            Operation::Product => self.values.iter().product(),
-        }
+    /// The numbers of this problem, joined by its operator.
+    fn expression(&self) -> String {
+        let values = self.values.iter().map(u64::to_string).collect::<Vec<_>>();
+        values.join(&format!(" {} ", self.operation))
+    }
+
+    /// Combine the numbers of this problem, in the order they were read.
+    fn solve(&self) -> Result<u128> {
+        let (first, rest) = self
+            .values
+            .split_first()
+            .ok_or_eyre("problem has no numbers")?;
+        rest.iter()
+            .try_fold(u128::from(*first), |lhs, &rhs| {
+                self.operation.apply(lhs, rhs.into())
+            })
+            .wrap_err_with(|| {
+                format!(
+                    "solving problem at {}: {}",
+                    self.location,
+                    self.expression()
+                )
+            })
     }
 }
 
-pub fn part1(input: &Path) -> Result<()> {
-    let input = Input::parse(input)?;
-    let grand_total = input.problems().map(|problem| problem.solve()).sum::<u64>();
+fn grand_total(input: &Path, reading: Reading, orientation: Orientation) -> Result<u128> {
+    let worksheet = Worksheet::from_path(input, orientation.operators)?;
+    let problems = worksheet.problems(reading, orientation)?;
+    problems.iter().try_fold(0_u128, |total, problem| {
+        total
+            .checked_add(problem.solve()?)
+            .ok_or_else(|| eyre!("grand total overflows u128 at problem {}", problem.location))
+    })
+}
+
+/// Read each line parallel to the operators as a number.
+pub fn part1(input: &Path, orientation: Orientation) -> Result<()> {
+    let reading = Reading::along(orientation.operators);
+    let grand_total = grand_total(input, reading, orientation)?;
     println!("grand total: {grand_total}");
     Ok(())
 }
 
-struct InputPt2 {
-    problems: Vec<Problem>,
+/// Read each line perpendicular to the operators as a number.
+pub fn part2(input: &Path, orientation: Orientation) -> Result<()> {
+    let reading = Reading::across(orientation.operators);
+    let grand_total = grand_total(input, reading, orientation)?;
+    println!("grand total (pt2): {grand_total}");
+    Ok(())
 }
 
-impl InputPt2 {
-    fn parse_problem(
-        lines: &[Vec<u8>],
-        operation: Operation,
-        range: impl Iterator<Item = usize>,
-    ) -> Result<Problem> {
-        let mut problem = Problem {
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solve(operation: Operation, values: &[u64]) -> Result<u128> {
+        Problem {
             operation,
-            values: Vec::new(),
-        };
-
-        let mut consecutive_empty_lines = 0;
-        for byte_column in range {
-            let value = lines
-                .iter()
-                .map(|line| {
-                    line.get(byte_column)
-                        .map(|byte| *byte as char)
-                        .unwrap_or(' ')
-                })
-                .collect::<String>();
-            let value = value.trim();
-            if value.is_empty() {
-                consecutive_empty_lines += 1;
-                if consecutive_empty_lines > 1 {
-                    break;
-                }
-                continue;
-            } else {
-                consecutive_empty_lines = 0;
-            }
-            problem
-                .values
-                .push(value.parse().wrap_err("invalid value")?);
+            location: Location {
+                rows: 0..2,
+                columns: 0..1,
+                placement: Placement::Bottom,
+            },
+            values: values.to_vec(),
         }
-
-        Ok(problem)
+        .solve()
     }
 
-    fn parse(input: &Path) -> Result<Self> {
-        let file = std::fs::File::open(input)?;
-        let reader = BufReader::new(file);
-        let mut lines = reader
-            .lines()
-            .map(|line| line.map(String::into_bytes).map_err(Into::into))
-            .collect::<Result<Vec<_>>>()?;
-        let operations_line = lines.pop().ok_or_eyre("no operations line in input")?;
-
-        let mut problems = Vec::new();
-        let mut end_of_previous = 0;
-        for (idx, byte) in operations_line.iter().copied().enumerate().skip(1) {
-            if byte.is_ascii_whitespace() {
-                continue; // not a new field
-            }
-            let operation = match operations_line[end_of_previous] {
-                b'+' => Operation::Sum,
-                b'*' => Operation::Product,
-                c => return Err(eyre!("invalid operation: {}", c as char)),
-            };
-
-            problems.push(Self::parse_problem(
-                &lines,
-                operation,
-                end_of_previous..idx,
-            )?);
-            end_of_previous = idx;
-        }
-
-        // of course we have not yet pushed the trailing values
-        let operation = match operations_line[end_of_previous] {
-            b'+' => Operation::Sum,
-            b'*' => Operation::Product,
-            c => return Err(eyre!("invalid operation: {}", c as char)),
-        };
-        problems.push(Self::parse_problem(&lines, operation, end_of_previous..)?);
-
-        Ok(Self { problems })
+    #[test]
+    fn operations_fold_in_reading_order() {
+        use Operation::*;
+        assert_eq!(solve(Sum, &[123, 45, 6]).unwrap(), 174);
+        assert_eq!(solve(Product, &[123, 45, 6]).unwrap(), 33210);
+        assert_eq!(solve(Difference, &[12, 5, 3]).unwrap(), 4);
+        assert_eq!(solve(Quotient, &[100, 7, 2]).unwrap(), 7);
+        assert_eq!(solve(Min, &[5, 3, 9]).unwrap(), 3);
+        assert_eq!(solve(Max, &[5, 3, 9]).unwrap(), 9);
+        assert_eq!(solve(Concatenation, &[12, 0, 345]).unwrap(), 120345);
+        assert_eq!(solve(Difference, &[7]).unwrap(), 7);
     }
-}
 
-pub fn part2(input: &Path) -> Result<()> {
-    let input = InputPt2::parse(input)?;
-    let grand_total = input
-        .problems
-        .iter()
-        .map(|problem| problem.solve())
-        .sum::<u64>();
-    println!("grand total (pt2): {grand_total}");
-    Ok(())
+    #[test]
+    fn errors_name_the_problem() {
+        use Operation::*;
+        let chain = format!("{:#}", solve(Difference, &[3, 5]).unwrap_err());
+        assert!(chain.contains("problem at column 1: 3 - 5"), "{chain}");
+        assert!(chain.contains("3 - 5 is negative"), "{chain}");
+
+        let chain = format!("{:#}", solve(Quotient, &[3, 0]).unwrap_err());
+        assert!(chain.contains("divides by zero"), "{chain}");
+
+        // the product of two u64s always fits, but three need not
+        assert_eq!(
+            solve(Product, &[u64::MAX, u64::MAX]).unwrap(),
+            u128::from(u64::MAX) * u128::from(u64::MAX)
+        );
+        let chain = format!("{:#}", solve(Product, &[u64::MAX; 3]).unwrap_err());
+        assert!(chain.contains("product overflows u128"), "{chain}");
+
+        let chain = format!("{:#}", solve(Concatenation, &[u64::MAX; 3]).unwrap_err());
+        assert!(chain.contains("concatenation overflows u128"), "{chain}");
+    }
 }
@@ -1,5 +1,5 @@
 use aoclib::{config::Config, website::get_input};
-use day06::{part1, part2};
+use day06::{annotate, part1, part2, Orientation, Part};
 
 use clap::Parser;
 use color_eyre::eyre::Result;
@@ -22,6 +22,13 @@ struct RunArgs {
     /// run part 2
     #[arg(long)]
     part2: bool,
+
+    #[command(flatten)]
+    orientation: Orientation,
+
+    /// print the worksheet with each problem's result, reading numbers as in this part, instead of running the puzzle parts
+    #[arg(long, value_name = "PART")]
+    annotate: Option<Part>,
 }
 
 impl RunArgs {
@@ -44,11 +51,15 @@ fn main() -> Result<()> {
     let args = RunArgs::parse();
     let input_path = args.input()?;
 
+    if let Some(part) = args.annotate {
+        return annotate(&input_path, part, args.orientation);
+    }
+
     if !args.no_part1 {
-        part1(&input_path)?;
+        part1(&input_path, args.orientation)?;
     }
     if args.part2 {
-        part2(&input_path)?;
+        part2(&input_path, args.orientation)?;
     }
     Ok(())
 }
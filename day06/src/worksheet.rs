@@ -0,0 +1,499 @@
+//! The worksheet as laid out on the page.
+//!
+//! A worksheet is a block of numbers beside a single line of operators, usually the bottom row. Problems sit
+//! side by side along the operator line, separated by lines which are blank across the whole worksheet. Each
+//! problem has exactly one operator somewhere beside it.
+//!
+//! We keep each problem's position and the raw characters of its numbers, so that the numbers can be read
+//! from them in more than one way: part 1 reads each line parallel to the operators as a number, while part 2
+//! reads each line perpendicular to them.
+
+use crate::{Operation, Problem};
+use color_eyre::{
+    eyre::{eyre, Context},
+    Result,
+};
+use std::{
+    fmt,
+    io::{BufRead, BufReader},
+    ops::Range,
+    path::Path,
+};
+
+/// The edge of the worksheet holding the operators.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Placement {
+    /// the last row, with problems side by side
+    #[default]
+    Bottom,
+    /// the first row, with problems side by side
+    Top,
+    /// the first column, with problems stacked one above another
+    Left,
+    /// the last column, with problems stacked one above another
+    Right,
+}
+
+impl Placement {
+    /// `true` when problems are stacked vertically, so that the worksheet is the transpose of the usual layout.
+    fn is_transposed(self) -> bool {
+        matches!(self, Self::Left | Self::Right)
+    }
+}
+
+/// How the worksheet is laid out and which way its numbers are read.
+#[derive(Debug, Default, Clone, Copy, clap::Args)]
+pub struct Orientation {
+    /// the edge of the worksheet holding the operators
+    #[arg(long, value_enum, default_value_t)]
+    pub operators: Placement,
+
+    /// read rows from right to left, and take columns starting from the rightmost
+    #[arg(long)]
+    pub right_to_left: bool,
+
+    /// read columns from bottom to top, and take rows starting from the lowest
+    #[arg(long)]
+    pub bottom_to_top: bool,
+}
+
+/// Which lines of a problem's block each hold one number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Reading {
+    Rows,
+    Columns,
+}
+
+impl Reading {
+    /// Lines parallel to the operators, as in part 1.
+    pub(crate) fn along(placement: Placement) -> Self {
+        if placement.is_transposed() {
+            Self::Columns
+        } else {
+            Self::Rows
+        }
+    }
+
+    /// Lines perpendicular to the operators, as in part 2.
+    pub(crate) fn across(placement: Placement) -> Self {
+        if placement.is_transposed() {
+            Self::Rows
+        } else {
+            Self::Columns
+        }
+    }
+}
+
+/// Name a run of rows or columns of the worksheet, which count from 0.
+fn describe(unit: &str, lines: &Range<usize>) -> String {
+    // people count lines from 1, as editors do
+    if lines.len() == 1 {
+        format!("{unit} {}", lines.start + 1)
+    } else {
+        format!("{unit}s {}-{}", lines.start + 1, lines.end)
+    }
+}
+
+/// Where a problem's numbers sit on the worksheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Location {
+    pub(crate) rows: Range<usize>,
+    pub(crate) columns: Range<usize>,
+    pub(crate) placement: Placement,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.placement.is_transposed() {
+            f.write_str(&describe("row", &self.rows))
+        } else {
+            f.write_str(&describe("column", &self.columns))
+        }
+    }
+}
+
+/// A problem as it appears on the worksheet, before its numbers have been read.
+#[derive(Debug, Clone)]
+pub(crate) struct Block {
+    pub(crate) operation: Operation,
+    pub(crate) location: Location,
+    /// The index of the operator along the operator line.
+    pub(crate) operator: usize,
+    /// The characters of the numbers, row by row, excluding the operator line.
+    pub(crate) cells: Vec<Vec<char>>,
+}
+
+impl Block {
+    /// Read the numbers of this problem.
+    pub(crate) fn read(&self, reading: Reading, orientation: Orientation) -> Result<Problem> {
+        let mut cells = self.cells.clone();
+        if orientation.right_to_left {
+            cells.iter_mut().for_each(|row| row.reverse());
+        }
+        if orientation.bottom_to_top {
+            cells.reverse();
+        }
+        let lines: Vec<String> = match reading {
+            Reading::Rows => cells.iter().map(|row| row.iter().collect()).collect(),
+            Reading::Columns => (0..self.location.columns.len())
+                .map(|column| cells.iter().map(|row| row[column]).collect())
+                .collect(),
+        };
+
+        // name each line as it appears on the page, whichever way it was read
+        let Location { rows, columns, .. } = &self.location;
+        let line_name = |idx: usize| {
+            let (unit, page_idx) = match reading {
+                Reading::Rows if orientation.bottom_to_top => ("row", rows.end - 1 - idx),
+                Reading::Rows => ("row", rows.start + idx),
+                Reading::Columns if orientation.right_to_left => ("column", columns.end - 1 - idx),
+                Reading::Columns => ("column", columns.start + idx),
+            };
+            describe(unit, &(page_idx..page_idx + 1))
+        };
+
+        let values = lines
+            .into_iter()
+            .enumerate()
+            .map(|(idx, line)| {
+                let value = line.trim();
+                if value.is_empty() {
+                    return Err(eyre!("{} holds no digits", line_name(idx)));
+                }
+                value
+                    .parse()
+                    .wrap_err_with(|| format!("{}: invalid value {value:?}", line_name(idx)))
+            })
+            .collect::<Result<_>>()
+            .wrap_err_with(|| format!("reading problem at {}", self.location))?;
+        Ok(Problem {
+            operation: self.operation,
+            location: self.location.clone(),
+            values,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Worksheet {
+    pub(crate) placement: Placement,
+    /// Every row of the page, padded with spaces to the same width.
+    pub(crate) grid: Vec<Vec<char>>,
+    pub(crate) blocks: Vec<Block>,
+}
+
+impl Worksheet {
+    pub(crate) fn from_reader(reader: impl BufRead, placement: Placement) -> Result<Self> {
+        let mut grid = reader
+            .lines()
+            .map(|line| {
+                line.map(|line| line.chars().collect::<Vec<_>>())
+                    .map_err(Into::into)
+            })
+            .collect::<Result<Vec<_>>>()
+            .wrap_err("reading worksheet")?;
+        while grid.last().is_some_and(|line| line.is_empty()) {
+            grid.pop();
+        }
+
+        // pad every row to the same width, so that columns line up even where trailing spaces were trimmed
+        let width = grid.iter().map(Vec::len).max().unwrap_or_default();
+        for row in &mut grid {
+            row.resize(width, ' ');
+        }
+        let height = grid.len();
+        let (along, across) = if placement.is_transposed() {
+            (height, width)
+        } else {
+            (width, height)
+        };
+        if along == 0 {
+            return Err(eyre!("no operators in input"));
+        }
+        if across < 2 {
+            return Err(eyre!("no numbers beside the operators"));
+        }
+
+        // the operator line runs along one edge of the worksheet; numbers fill the rest
+        let (operators, number_rows, number_columns): (Vec<char>, _, _) = match placement {
+            Placement::Bottom => (grid[height - 1].clone(), 0..height - 1, 0..width),
+            Placement::Top => (grid[0].clone(), 1..height, 0..width),
+            Placement::Left => (grid.iter().map(|row| row[0]).collect(), 0..height, 1..width),
+            Placement::Right => (
+                grid.iter().map(|row| row[width - 1]).collect(),
+                0..height,
+                0..width - 1,
+            ),
+        };
+        let unit = if placement.is_transposed() {
+            "row"
+        } else {
+            "column"
+        };
+
+        let is_separator = |position: usize| {
+            operators[position].is_whitespace()
+                && if placement.is_transposed() {
+                    grid[position][number_columns.clone()]
+                        .iter()
+                        .all(|char| char.is_whitespace())
+                } else {
+                    number_rows
+                        .clone()
+                        .all(|row| grid[row][position].is_whitespace())
+                }
+        };
+
+        let mut blocks = Vec::new();
+        let mut position = 0;
+        while position < along {
+            if is_separator(position) {
+                position += 1;
+                continue;
+            }
+            let start = position;
+            while position < along && !is_separator(position) {
+                position += 1;
+            }
+            let span = start..position;
+            let location = if placement.is_transposed() {
+                Location {
+                    rows: span.clone(),
+                    columns: number_columns.clone(),
+                    placement,
+                }
+            } else {
+                Location {
+                    rows: number_rows.clone(),
+                    columns: span.clone(),
+                    placement,
+                }
+            };
+
+            let mut operations = operators[span]
+                .iter()
+                .enumerate()
+                .filter(|(_, char)| !char.is_whitespace())
+                .map(|(offset, char)| {
+                    let operator = start + offset;
+                    char.to_string()
+                        .parse::<Operation>()
+                        .map(|operation| (operation, operator))
+                        .map_err(|_| eyre!("invalid operation {char:?} in {unit} {}", operator + 1))
+                });
+            let (operation, operator) = match (operations.next(), operations.next()) {
+                (None, _) => Err(eyre!("{location}: no operator beside this problem")),
+                (Some(operation), None) => operation,
+                (Some(_), Some(_)) => Err(eyre!(
+                    "{location}: more than one operator beside this problem; is it misaligned?"
+                )),
+            }?;
+
+            let cells = grid[location.rows.clone()]
+                .iter()
+                .map(|row| row[location.columns.clone()].to_vec())
+                .collect();
+            blocks.push(Block {
+                operation,
+                location,
+                operator,
+                cells,
+            });
+        }
+
+        Ok(Self {
+            placement,
+            grid,
+            blocks,
+        })
+    }
+
+    pub(crate) fn from_path(input: &Path, placement: Placement) -> Result<Self> {
+        let file = std::fs::File::open(input).wrap_err("opening input file")?;
+        Self::from_reader(BufReader::new(file), placement)
+    }
+
+    /// Read the numbers of every problem.
+    pub(crate) fn problems(
+        &self,
+        reading: Reading,
+        orientation: Orientation,
+    ) -> Result<Vec<Problem>> {
+        self.blocks
+            .iter()
+            .map(|block| block.read(reading, orientation))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "123 328  51 64 \n 45 64  387 23 \n  6 98  215 314\n*   +   *   +  \n";
+
+    fn parse(input: &str, placement: Placement) -> Worksheet {
+        Worksheet::from_reader(input.as_bytes(), placement).unwrap()
+    }
+
+    fn values(worksheet: &Worksheet, reading: Reading, orientation: Orientation) -> Vec<Vec<u64>> {
+        worksheet
+            .problems(reading, orientation)
+            .unwrap()
+            .into_iter()
+            .map(|problem| problem.values)
+            .collect()
+    }
+
+    /// Swap the rows and columns of a worksheet.
+    fn transpose(input: &str) -> String {
+        let rows = input
+            .lines()
+            .map(|line| line.chars().collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+        (0..width)
+            .map(|column| {
+                let line = rows
+                    .iter()
+                    .map(|row| row.get(column).copied().unwrap_or(' '))
+                    .collect::<String>();
+                format!("{line}\n")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn example() {
+        let worksheet = parse(EXAMPLE, Placement::Bottom);
+        let columns = worksheet
+            .blocks
+            .iter()
+            .map(|block| block.location.columns.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(columns, [0..3, 4..7, 8..11, 12..15]);
+        let orientation = Orientation::default();
+        assert_eq!(
+            values(&worksheet, Reading::Rows, orientation),
+            [
+                vec![123, 45, 6],
+                vec![328, 64, 98],
+                vec![51, 387, 215],
+                vec![64, 23, 314]
+            ]
+        );
+        assert_eq!(
+            values(&worksheet, Reading::Columns, orientation),
+            [
+                vec![1, 24, 356],
+                vec![369, 248, 8],
+                vec![32, 581, 175],
+                vec![623, 431, 4]
+            ]
+        );
+
+        // trailing spaces are often trimmed by editors
+        let trimmed = EXAMPLE.replace(" \n", "\n").replace(" \n", "\n");
+        let trimmed = parse(&trimmed, Placement::Bottom);
+        assert_eq!(
+            values(&trimmed, Reading::Columns, orientation),
+            values(&worksheet, Reading::Columns, orientation)
+        );
+    }
+
+    #[test]
+    fn orientations() {
+        let worksheet = parse(EXAMPLE, Placement::Bottom);
+        let right_to_left = Orientation {
+            right_to_left: true,
+            ..Orientation::default()
+        };
+        let bottom_to_top = Orientation {
+            bottom_to_top: true,
+            ..Orientation::default()
+        };
+        assert_eq!(
+            values(&worksheet, Reading::Columns, right_to_left)[3],
+            [4, 431, 623]
+        );
+        assert_eq!(
+            values(&worksheet, Reading::Rows, right_to_left)[3],
+            [46, 32, 413]
+        );
+        assert_eq!(
+            values(&worksheet, Reading::Rows, bottom_to_top)[0],
+            [6, 45, 123]
+        );
+        assert_eq!(
+            values(&worksheet, Reading::Columns, bottom_to_top)[0],
+            [1, 42, 653]
+        );
+
+        // moving the operators to another edge, or transposing the page, does not change the numbers
+        let operators_first = {
+            let mut lines = EXAMPLE.lines().collect::<Vec<_>>();
+            lines.rotate_right(1);
+            lines.join("\n")
+        };
+        let default = Orientation::default();
+        for (input, placement) in [
+            (operators_first.clone(), Placement::Top),
+            (transpose(&operators_first), Placement::Left),
+            (transpose(EXAMPLE), Placement::Right),
+        ] {
+            let placed = parse(&input, placement);
+            assert_eq!(
+                values(&placed, Reading::along(placement), default),
+                values(&worksheet, Reading::Rows, default),
+                "{placement:?}"
+            );
+            assert_eq!(
+                values(&placed, Reading::across(placement), default),
+                values(&worksheet, Reading::Columns, default),
+                "{placement:?}"
+            );
+        }
+
+        let chain = format!(
+            "{:#}",
+            Worksheet::from_reader(
+                transpose("12 34\n5 678\n*  +\n").as_bytes(),
+                Placement::Right
+            )
+            .unwrap_err()
+        );
+        assert!(
+            chain.contains("rows 1-5: more than one operator"),
+            "{chain}"
+        );
+    }
+
+    #[test]
+    fn misaligned_columns() {
+        let err = |input: &str| {
+            Worksheet::from_reader(input.as_bytes(), Placement::Bottom)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            err("12 34\n5 678\n*  +\n"),
+            "columns 1-5: more than one operator beside this problem; is it misaligned?"
+        );
+        assert_eq!(
+            err("12 34\n5  6\n*\n"),
+            "columns 4-5: no operator beside this problem"
+        );
+        assert_eq!(
+            err("12 34\n5  6\n*  ?\n"),
+            "invalid operation '?' in column 4"
+        );
+        assert_eq!(err("1\n"), "no numbers beside the operators");
+
+        let err = Worksheet::from_reader("12 34\n   6\n*  +\n".as_bytes(), Placement::Bottom)
+            .unwrap()
+            .problems(Reading::Rows, Orientation::default())
+            .unwrap_err();
+        assert_eq!(err.to_string(), "reading problem at columns 1-2");
+        assert_eq!(err.root_cause().to_string(), "row 2 holds no digits");
+    }
+}
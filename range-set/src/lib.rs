@@ -0,0 +1,498 @@
+//! A set of integers, stored as sorted, disjoint inclusive ranges.
+//!
+//! No two ranges in a set overlap or are even adjacent: inserting `3..=5` into a set containing `1..=2`
+//! produces the single range `1..=5`. This canonical form makes membership a binary search, and lets the
+//! set operations walk both operands in order.
+//!
+//! Ranges are accepted and produced as [`RangeInclusive`], so this is usable with any day's inclusive ranges.
+//! Empty ranges are ignored. Arithmetic at the ends of the domain is checked, so a range may extend all the
+//! way to `T::MAX`.
+
+use std::{fmt, ops::RangeInclusive};
+
+/// An integer type which can bound the ranges of a [`RangeSet`].
+pub trait Endpoint: Copy + Ord + fmt::Debug {
+    const MIN: Self;
+    const MAX: Self;
+
+    /// The next value, or `None` at `Self::MAX`.
+    fn checked_next(self) -> Option<Self>;
+
+    /// The previous value, or `None` at `Self::MIN`.
+    fn checked_prev(self) -> Option<Self>;
+
+    /// The number of values in `low..=high`, which must not be empty.
+    ///
+    /// Returns `None` if the count exceeds `u128::MAX`, which is only possible for 128-bit types.
+    fn span(low: Self, high: Self) -> Option<u128>;
+}
+
+macro_rules! impl_endpoint {
+    ($($t:ty),*; |$low:ident, $high:ident| $span:expr) => {
+        $(
+            impl Endpoint for $t {
+                const MIN: Self = <$t>::MIN;
+                const MAX: Self = <$t>::MAX;
+
+                fn checked_next(self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+
+                fn checked_prev(self) -> Option<Self> {
+                    self.checked_sub(1)
+                }
+
+                fn span($low: Self, $high: Self) -> Option<u128> {
+                    $span
+                }
+            }
+        )*
+    };
+}
+
+// every value of these types fits in an `i128`, so the count always fits in a `u128`
+impl_endpoint!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize; |low, high| {
+    Some((high as i128 - low as i128) as u128 + 1)
+});
+
+// the difference between two values always fits in a `u128`, but the count of the whole domain does not
+impl_endpoint!(u128, i128; |low, high| {
+    high.abs_diff(low).checked_add(1)
+});
+
+/// `true` when a range ending at `high` overlaps or is adjacent to a range starting at `low`.
+fn touches<T: Endpoint>(high: T, low: T) -> bool {
+    high.checked_next().is_none_or(|next| low <= next)
+}
+
+/// A set of integers, stored as sorted, disjoint inclusive ranges.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct RangeSet<T> {
+    /// `(low, high)` pairs, sorted, with at least one value absent between each.
+    ranges: Vec<(T, T)>,
+}
+
+impl<T> Default for RangeSet<T> {
+    fn default() -> Self {
+        Self { ranges: Vec::new() }
+    }
+}
+
+impl<T: Endpoint> fmt::Debug for RangeSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Endpoint> RangeSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a range which starts no earlier than every range already in the set.
+    fn push(&mut self, low: T, high: T) {
+        match self.ranges.last_mut() {
+            Some((_, previous_high)) if touches(*previous_high, low) => {
+                *previous_high = (*previous_high).max(high);
+            }
+            _ => self.ranges.push((low, high)),
+        }
+    }
+
+    /// Add every value in `range` to the set.
+    pub fn insert(&mut self, range: RangeInclusive<T>) {
+        let (low, high) = range.into_inner();
+        if low > high {
+            return;
+        }
+        // `[first, last)` are the ranges which overlap or are adjacent to the new one
+        let first = self
+            .ranges
+            .partition_point(|&(_, existing_high)| !touches(existing_high, low));
+        let last = self
+            .ranges
+            .partition_point(|&(existing_low, _)| touches(high, existing_low));
+
+        let (merged_low, merged_high) = if first < last {
+            (
+                low.min(self.ranges[first].0),
+                high.max(self.ranges[last - 1].1),
+            )
+        } else {
+            (low, high)
+        };
+        self.ranges.splice(first..last, [(merged_low, merged_high)]);
+    }
+
+    /// Remove every value in `range` from the set.
+    pub fn remove(&mut self, range: RangeInclusive<T>) {
+        let (low, high) = range.into_inner();
+        if low > high {
+            return;
+        }
+        // `[first, last)` are the ranges which overlap the removed one
+        let first = self
+            .ranges
+            .partition_point(|&(_, existing_high)| existing_high < low);
+        let last = self
+            .ranges
+            .partition_point(|&(existing_low, _)| existing_low <= high);
+        if first >= last {
+            return;
+        }
+
+        let mut remnants = Vec::with_capacity(2);
+        let (first_low, _) = self.ranges[first];
+        if first_low < low {
+            let before = low.checked_prev().expect("a value less than `low` exists");
+            remnants.push((first_low, before));
+        }
+        let (_, last_high) = self.ranges[last - 1];
+        if high < last_high {
+            let after = high
+                .checked_next()
+                .expect("a value greater than `high` exists");
+            remnants.push((after, last_high));
+        }
+        self.ranges.splice(first..last, remnants);
+    }
+
+    /// The range of the set which contains `value`, if any.
+    pub fn range_containing(&self, value: T) -> Option<RangeInclusive<T>> {
+        let idx = self.ranges.partition_point(|&(_, high)| high < value);
+        self.ranges
+            .get(idx)
+            .filter(|&&(low, _)| low <= value)
+            .map(|&(low, high)| low..=high)
+    }
+
+    pub fn contains(&self, value: T) -> bool {
+        self.range_containing(value).is_some()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// The total number of values in the set.
+    ///
+    /// Returns `None` if the count exceeds `u128::MAX`, which is only possible when a set of a 128-bit type
+    /// contains every value.
+    pub fn count(&self) -> Option<u128> {
+        self.ranges.iter().try_fold(0_u128, |count, &(low, high)| {
+            count.checked_add(T::span(low, high)?)
+        })
+    }
+
+    /// The ranges of the set, in ascending order.
+    pub fn iter(
+        &self,
+    ) -> impl '_ + DoubleEndedIterator<Item = RangeInclusive<T>> + ExactSizeIterator {
+        self.ranges.iter().map(|&(low, high)| low..=high)
+    }
+
+    /// The ranges of values between consecutive ranges of the set, in ascending order.
+    ///
+    /// This does not include values before the first range or after the last; see [`Self::complement`].
+    pub fn gaps(
+        &self,
+    ) -> impl '_ + DoubleEndedIterator<Item = RangeInclusive<T>> + ExactSizeIterator {
+        self.ranges.windows(2).map(|window| {
+            let (_, previous_high) = window[0];
+            let (next_low, _) = window[1];
+            let low = previous_high
+                .checked_next()
+                .expect("ranges are not adjacent");
+            let high = next_low.checked_prev().expect("ranges are not adjacent");
+            low..=high
+        })
+    }
+
+    /// Every value not in the set.
+    pub fn complement(&self) -> Self {
+        let mut complement = Self::new();
+        let mut next_low = Some(T::MIN);
+        for &(low, high) in &self.ranges {
+            if let Some(next_low) = next_low
+                && let Some(before) = low.checked_prev()
+            {
+                complement.push(next_low, before);
+            }
+            next_low = high.checked_next();
+        }
+        if let Some(next_low) = next_low {
+            complement.push(next_low, T::MAX);
+        }
+        complement
+    }
+
+    /// Every value in either set.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = Self::new();
+        let mut left = self.ranges.iter().copied().peekable();
+        let mut right = other.ranges.iter().copied().peekable();
+        while let Some((low, high)) = match (left.peek(), right.peek()) {
+            (Some(l), Some(r)) if l <= r => left.next(),
+            (Some(_), Some(_)) => right.next(),
+            (Some(_), None) => left.next(),
+            (None, _) => right.next(),
+        } {
+            union.push(low, high);
+        }
+        union
+    }
+
+    /// Every value in both sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intersection = Self::new();
+        let (mut left, mut right) = (0, 0);
+        while let (Some(&(left_low, left_high)), Some(&(right_low, right_high))) =
+            (self.ranges.get(left), other.ranges.get(right))
+        {
+            let low = left_low.max(right_low);
+            let high = left_high.min(right_high);
+            if low <= high {
+                intersection.ranges.push((low, high));
+            }
+            // whichever range ends first cannot intersect anything further in the other set
+            if left_high < right_high {
+                left += 1;
+            } else {
+                right += 1;
+            }
+        }
+        intersection
+    }
+
+    /// Every value in this set but not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.intersection(&other.complement())
+    }
+}
+
+impl<T: Endpoint> FromIterator<RangeInclusive<T>> for RangeSet<T> {
+    fn from_iter<I: IntoIterator<Item = RangeInclusive<T>>>(iter: I) -> Self {
+        let mut ranges = iter
+            .into_iter()
+            .map(RangeInclusive::into_inner)
+            .filter(|(low, high)| low <= high)
+            .collect::<Vec<_>>();
+        ranges.sort_unstable();
+
+        let mut set = Self::new();
+        for (low, high) in ranges {
+            set.push(low, high);
+        }
+        set
+    }
+}
+
+impl<T: Endpoint> Extend<RangeInclusive<T>> for RangeSet<T> {
+    fn extend<I: IntoIterator<Item = RangeInclusive<T>>>(&mut self, iter: I) {
+        *self = self.union(&iter.into_iter().collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use xorshift::XorShift;
+
+    /// Membership of every `u8`, for comparison against a set.
+    type Bitmap = [bool; 256];
+
+    fn bitmap(set: &RangeSet<u8>) -> Bitmap {
+        let mut bitmap = [false; 256];
+        for range in set.iter() {
+            for value in range {
+                bitmap[usize::from(value)] = true;
+            }
+        }
+        bitmap
+    }
+
+    fn assert_canonical(set: &RangeSet<u8>) {
+        for &(low, high) in &set.ranges {
+            assert!(low <= high, "empty range in {set:?}");
+        }
+        for window in set.ranges.windows(2) {
+            assert!(
+                !touches(window[0].1, window[1].0),
+                "ranges overlap or are adjacent in {set:?}"
+            );
+        }
+    }
+
+    /// A range biased towards the extremes of the domain and towards short lengths.
+    fn random_range(rng: &mut XorShift) -> RangeInclusive<u8> {
+        let low = match rng.next_u64() % 8 {
+            0 => 0,
+            1 => u8::MAX,
+            _ => rng.next_u64() as u8,
+        };
+        let len = match rng.next_u64() % 4 {
+            0 => rng.next_u64() as u8,
+            _ => rng.next_u64() as u8 % 8,
+        };
+        // occasionally empty
+        if rng.next_u64().is_multiple_of(16) {
+            return low..=low.wrapping_sub(1);
+        }
+        low..=low.saturating_add(len)
+    }
+
+    fn random_set(rng: &mut XorShift) -> (RangeSet<u8>, Bitmap) {
+        let mut set = RangeSet::new();
+        let mut expect = [false; 256];
+        for _ in 0..rng.next_u64() % 10 {
+            let range = random_range(rng);
+            for value in range.clone() {
+                expect[usize::from(value)] = true;
+            }
+            set.insert(range);
+        }
+        (set, expect)
+    }
+
+    #[test]
+    fn insert_and_remove_match_bitmap() {
+        let mut rng = XorShift::new(0x5eed);
+        for _ in 0..500 {
+            let mut set = RangeSet::new();
+            let mut expect = [false; 256];
+            for _ in 0..20 {
+                let range = random_range(&mut rng);
+                let insert = !rng.next_u64().is_multiple_of(3);
+                for value in range.clone() {
+                    expect[usize::from(value)] = insert;
+                }
+                if insert {
+                    set.insert(range);
+                } else {
+                    set.remove(range);
+                }
+                assert_canonical(&set);
+                assert_eq!(bitmap(&set), expect, "{set:?}");
+            }
+
+            for value in 0..=u8::MAX {
+                assert_eq!(set.contains(value), expect[usize::from(value)]);
+                if let Some(range) = set.range_containing(value) {
+                    assert!(range.contains(&value));
+                    assert!(set.iter().any(|r| r == range));
+                }
+            }
+            let expect_count = expect.iter().filter(|&&present| present).count() as u128;
+            assert_eq!(set.count(), Some(expect_count));
+        }
+    }
+
+    #[test]
+    fn set_operations_match_bitmap() {
+        let mut rng = XorShift::new(0xfeed);
+        for _ in 0..500 {
+            let (left, left_bits) = random_set(&mut rng);
+            let (right, right_bits) = random_set(&mut rng);
+
+            type Op = fn(bool, bool) -> bool;
+            let cases: [(&str, RangeSet<u8>, Op); 3] = [
+                ("union", left.union(&right), |l, r| l || r),
+                ("intersection", left.intersection(&right), |l, r| l && r),
+                ("difference", left.difference(&right), |l, r| l && !r),
+            ];
+            for (name, result, op) in cases {
+                assert_canonical(&result);
+                let expect: Bitmap = std::array::from_fn(|idx| op(left_bits[idx], right_bits[idx]));
+                assert_eq!(bitmap(&result), expect, "{name} of {left:?} and {right:?}");
+            }
+
+            let complement = left.complement();
+            assert_canonical(&complement);
+            let expect: Bitmap = std::array::from_fn(|idx| !left_bits[idx]);
+            assert_eq!(bitmap(&complement), expect, "complement of {left:?}");
+
+            let mut extended = left.clone();
+            extended.extend(right.iter());
+            assert_eq!(extended, left.union(&right));
+        }
+    }
+
+    #[test]
+    fn gaps_lie_between_ranges() {
+        let mut rng = XorShift::new(0xbead);
+        for _ in 0..500 {
+            let (set, bits) = random_set(&mut rng);
+            let gaps = set.gaps().collect::<RangeSet<_>>();
+            assert_canonical(&gaps);
+
+            let first = bits.iter().position(|&present| present);
+            let last = bits.iter().rposition(|&present| present);
+            let expect: Bitmap = std::array::from_fn(|idx| {
+                first.is_some_and(|first| first < idx)
+                    && last.is_some_and(|last| idx < last)
+                    && !bits[idx]
+            });
+            assert_eq!(bitmap(&gaps), expect, "gaps of {set:?}");
+            assert_eq!(set.gaps().count(), set.iter().len().saturating_sub(1));
+        }
+    }
+
+    #[test]
+    fn from_iter_consolidates() {
+        #[allow(clippy::reversed_empty_ranges)]
+        let set = [10..=14, 3..=5, 16..=20, 12..=18, 6..=6, 30..=29]
+            .into_iter()
+            .collect::<RangeSet<u64>>();
+        assert_eq!(set.iter().collect::<Vec<_>>(), [3..=6, 10..=20]);
+        assert_eq!(set.count(), Some(15));
+        assert_eq!(set.gaps().collect::<Vec<_>>(), [7..=9]);
+    }
+
+    #[test]
+    fn extreme_bounds() {
+        let mut set = RangeSet::<u64>::new();
+        set.insert(0..=u64::MAX);
+        assert_eq!(set.count(), Some(u128::from(u64::MAX) + 1));
+        assert!(set.contains(0) && set.contains(u64::MAX));
+        assert!(set.complement().is_empty());
+
+        set.remove(u64::MAX..=u64::MAX);
+        set.remove(0..=0);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [1..=u64::MAX - 1]);
+        assert_eq!(
+            set.complement().iter().collect::<Vec<_>>(),
+            [0..=0, u64::MAX..=u64::MAX]
+        );
+
+        set.insert(u64::MAX..=u64::MAX);
+        set.insert(0..=0);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0..=u64::MAX]);
+
+        let signed = [i64::MIN..=-1, 0..=i64::MAX]
+            .into_iter()
+            .collect::<RangeSet<_>>();
+        assert_eq!(signed.iter().collect::<Vec<_>>(), [i64::MIN..=i64::MAX]);
+        assert_eq!(signed.count(), Some(1 << 64));
+    }
+
+    #[test]
+    fn wide_bounds() {
+        let mut set = RangeSet::<u128>::new();
+        set.insert(1..=u128::MAX);
+        assert_eq!(set.count(), Some(u128::MAX));
+        set.insert(0..=0);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0..=u128::MAX]);
+        assert_eq!(set.count(), None);
+
+        set.remove(5..=5);
+        assert_eq!(set.count(), Some(u128::MAX));
+        assert_eq!(set.gaps().collect::<Vec<_>>(), [5..=5]);
+
+        let signed = [i128::MIN..=-1, 1..=i128::MAX]
+            .into_iter()
+            .collect::<RangeSet<_>>();
+        assert_eq!(signed.count(), Some(u128::MAX));
+        assert!(!signed.contains(0));
+        assert_eq!(signed.complement().iter().collect::<Vec<_>>(), [0..=0]);
+    }
+}